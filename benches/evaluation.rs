@@ -0,0 +1,64 @@
+//! Compares the stack interpreter in `execution::evaluate` against the register
+//! bytecode from `bytecode::compile` over every row of a few tables.
+//!
+//! Run with `cargo bench`
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use ttbl::{bytecode, compiler, execution, scanner, table::BoolPermutationsIterator};
+
+const FORMULAS: [&str; 4] = [
+    "{p or q} and !(p and {!q})",
+    "(a & b) | (a & b) | (c & d) | (c & d) | !(!(e => g)) | (h <=> i)",
+    "{a & b & c & d} | {e & g & h & i} | ({a & b & c & d} <=> {j | k | l | m}) | (n & TRUE)",
+    "((a => b) & (b => c) & (c => d) & (d => e)) => (a => e) & (g | !g) & (h <=> !h | i)",
+];
+
+const ITERATIONS: u32 = 20;
+
+fn time<F: FnMut()>(mut f: F) -> Duration
+{
+    let start = Instant::now();
+    for _ in 0..ITERATIONS { f(); }
+    start.elapsed() / ITERATIONS
+}
+
+fn main()
+{
+    for formula in FORMULAS
+    {
+        let tokens = scanner::tokenize(formula);
+        let compiled = compiler::compile(&tokens);
+        let groups = execution::subexpression_groups(compiled.root.as_ref().unwrap()).unwrap();
+        let var_count = compiled.variables.len() as u32;
+
+        let interpreted = time(|| {
+            let mut iter = BoolPermutationsIterator::new(var_count);
+            let mut row_results = vec![false; groups.len()];
+            while !iter.finished()
+            {
+                iter.advance();
+                execution::evaluate(&groups, iter.get(), &mut row_results[..]).unwrap();
+                black_box(&row_results);
+            }
+        });
+
+        let program = bytecode::compile(&groups).unwrap();
+        let bytecode = time(|| {
+            let mut iter = BoolPermutationsIterator::new(var_count);
+            let mut row_results = vec![false; groups.len()];
+            let mut registers = vec![false; program.register_count];
+            while !iter.finished()
+            {
+                iter.advance();
+                program.evaluate(iter.get(), &mut registers[..], &mut row_results[..]);
+                black_box(&row_results);
+            }
+        });
+
+        println!("{}", formula);
+        println!("    {} rows, {} instructions", 1u64 << var_count, program.instructions.len());
+        println!("    interpreter: {:>10.3?}", interpreted);
+        println!("    bytecode:    {:>10.3?}  ({:.2}x)", bytecode, interpreted.as_secs_f64() / bytecode.as_secs_f64());
+    }
+}
@@ -0,0 +1,331 @@
+use crate::bytecode;
+use crate::execution::{self, EvalError};
+use crate::formula::Formula;
+use crate::table::{self, RowOrdering, TruthTable};
+use std::thread;
+
+/// Largest number of variables `count_models` will enumerate, 2^24 rows take well under a second
+pub const MAX_COUNTED_VARIABLES: usize = 24;
+
+/// Blocks of 64 rows each worker thread of `count_models` handles at least
+const MIN_BLOCKS_PER_THREAD: u64 = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Classification
+{
+    /// True in every row
+    Tautology,
+    /// False in every row
+    Contradiction,
+    /// True in some rows and false in others
+    Contingent,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColumnSummary
+{
+    pub classification: Classification,
+    pub true_rows: usize,
+    pub false_rows: usize,
+}
+
+impl ColumnSummary
+{
+    /// A column is satisfiable when at least one row makes it true
+    pub fn is_satisfiable(&self) -> bool
+    {
+        return self.true_rows > 0;
+    }
+}
+
+impl std::fmt::Display for Classification
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self
+        {
+            Classification::Tautology => write!(f, "tautology"),
+            Classification::Contradiction => write!(f, "contradiction"),
+            Classification::Contingent => write!(f, "contingent"),
+        }
+    }
+}
+
+impl std::fmt::Display for ColumnSummary
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        write!(
+            f, "{}, {} ({} true, {} false)",
+            self.classification,
+            if self.is_satisfiable() { "satisfiable" } else { "unsatisfiable" },
+            self.true_rows,
+            self.false_rows
+        )
+    }
+}
+
+pub fn classify<I: IntoIterator<Item = bool>>(column: I) -> ColumnSummary
+{
+    let mut true_rows = 0;
+    let mut false_rows = 0;
+
+    for val in column
+    {
+        if val { true_rows += 1; } else { false_rows += 1; }
+    }
+
+    let classification = match (true_rows, false_rows)
+    {
+        (_, 0) => Classification::Tautology,
+        (0, _) => Classification::Contradiction,
+        _ => Classification::Contingent,
+    };
+
+    return ColumnSummary { classification, true_rows, false_rows };
+}
+
+/// Classifies every result column of `table`, in the order of the subexpression groups
+pub fn classify_table(table: &TruthTable) -> Vec<ColumnSummary>
+{
+    let columns = table.outputs.first().map(|row| row.len()).unwrap_or(0);
+
+    return (0..columns)
+        .map(|col| classify(table.outputs.iter().map(|row| row[col])))
+        .collect();
+}
+
+/**
+ * For every result column of a complete `table`, in any row order, which variables it depends on:
+ * `dependencies(table)[col][loc]` is set when flipping variable `loc` changes the column in at
+ * least one row. Variables a column does not depend on are vacuous for it
+**/
+pub fn dependencies(table: &TruthTable) -> Vec<Vec<bool>>
+{
+    let var_count = table.inputs.first().map(|row| row.len()).unwrap_or(0);
+    let columns = table.outputs.first().map(|row| row.len()).unwrap_or(0);
+
+    let mut position = vec![0; table.inputs.len()];
+    for (row, inputs) in table.inputs.iter().enumerate()
+    {
+        position[inputs.iter().fold(0, |acc, val| acc << 1 | *val as usize)] = row;
+    }
+
+    let mut depends = vec![vec![false; var_count]; columns];
+
+    for loc in 0..var_count
+    {
+        let bit = 1 << (var_count - loc - 1);
+
+        // Compare every row where the variable is false with its partner where it is true
+        for minterm in (0..position.len()).filter(|minterm| minterm & bit == 0)
+        {
+            let (low, high) = (&table.outputs[position[minterm]], &table.outputs[position[minterm | bit]]);
+
+            for (col, depends) in depends.iter_mut().enumerate()
+            {
+                depends[loc] |= low[col] != high[col];
+            }
+        }
+    }
+
+    return depends;
+}
+
+/**
+ * Truth vector (see `table::truth_vector`) of the Boolean derivative ∂f/∂x of the function `truth`
+ * of `size` variables with respect to variable `loc`: true where flipping the variable flips the
+ * function. It does not depend on the variable itself
+**/
+pub fn derivative(truth: &[bool], size: u32, loc: usize) -> Vec<bool>
+{
+    let bit = 1 << (size as usize - loc - 1);
+    return (0..truth.len()).map(|minterm| truth[minterm] != truth[minterm ^ bit]).collect();
+}
+
+/// How strongly a function responds to flipping single variables
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sensitivity
+{
+    /// Number of variables whose flip changes the function, per minterm
+    pub rows: Vec<u32>,
+    /// Fraction of the rows where flipping the variable changes the function, per variable
+    pub influence: Vec<f64>,
+    /// Mean of `rows`, also the sum of `influence`
+    pub average: f64,
+    /// Largest of `rows`
+    pub max: u32,
+}
+
+/// Influence of every variable and the sensitivity of every row of the function `truth` of `size` variables
+pub fn sensitivity(truth: &[bool], size: u32) -> Sensitivity
+{
+    let mut rows = vec![0u32; truth.len()];
+    let mut influence = vec![0.0; size as usize];
+
+    for (loc, influence) in influence.iter_mut().enumerate()
+    {
+        let changes = derivative(truth, size, loc);
+
+        for (row, changes) in rows.iter_mut().zip(changes.iter())
+        {
+            *row += *changes as u32;
+        }
+
+        *influence = changes.iter().filter(|changes| **changes).count() as f64 / truth.len() as f64;
+    }
+
+    let average = rows.iter().sum::<u32>() as f64 / truth.len() as f64;
+    let max = rows.iter().copied().max().unwrap_or(0);
+
+    return Sensitivity { rows, influence, average, max };
+}
+
+/// A row where two formulas disagree
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Difference
+{
+    pub assignment: Vec<bool>,
+    /// Value of the left formula, the right one has the opposite value
+    pub left: bool,
+}
+
+/**
+ * Returns every assignment (over the shared variables of both formulas, see
+ * `formula::parse_shared`) for which the two formulas disagree, in the given row order.
+ * The formulas are equivalent when there are none
+**/
+pub fn distinguishing_assignments(left: &Formula, right: &Formula, ordering: RowOrdering, threads: usize) -> Result<Vec<Difference>, EvalError>
+{
+    let var_count = left.variables.len().max(right.variables.len()) as u32;
+
+    let left_table = table::evaluate_table(&left.groups, var_count, ordering, threads)?;
+    let right_table = table::evaluate_table(&right.groups, var_count, ordering, threads)?;
+
+    let differences = left_table.inputs.into_iter()
+        .zip(left_table.outputs.iter().zip(right_table.outputs.iter()))
+        .filter(|(_, (left_row, right_row))| left_row.last() != right_row.last())
+        .map(|(assignment, (left_row, _))| Difference { assignment, left: left_row[left_row.len() - 1] })
+        .collect();
+
+    return Ok(differences);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowKind
+{
+    /// At least one premise is false
+    NotCritical,
+    /// A critical row (every premise is true) in which the conclusion is true as well
+    Critical,
+    /// A critical row in which the conclusion is false
+    Counterexample,
+}
+
+/// The joint truth table of an argument `premises |- conclusion`
+pub struct Argument
+{
+    /// One table per premise followed by the table of the conclusion, all over the same rows
+    pub tables: Vec<TruthTable>,
+    pub rows: Vec<RowKind>,
+}
+
+impl Argument
+{
+    /// An argument is valid when no row makes every premise true and the conclusion false
+    pub fn is_valid(&self) -> bool
+    {
+        return !self.rows.contains(&RowKind::Counterexample);
+    }
+}
+
+/**
+ * Evaluates the premises and conclusion of an argument over their shared variables (see
+ * `formula::parse_shared`) and marks the critical rows, those where every premise holds, as
+ * `Critical` or `Counterexample` depending on the conclusion
+**/
+pub fn check_argument(premises: &[Formula], conclusion: &Formula, ordering: RowOrdering, threads: usize) -> Result<Argument, EvalError>
+{
+    let var_count = conclusion.variables.len() as u32;
+
+    let tables = premises.iter()
+        .chain([conclusion])
+        .map(|formula| table::evaluate_table(&formula.groups, var_count, ordering, threads))
+        .collect::<Result<Vec<TruthTable>, EvalError>>()?;
+
+    let (conclusion_table, premise_tables) = tables.split_last().unwrap();
+
+    let rows = (0..conclusion_table.outputs.len())
+        .map(|row| {
+            let result = |table: &TruthTable| table.outputs[row][table.outputs[row].len() - 1];
+
+            if !premise_tables.iter().all(result) { return RowKind::NotCritical; }
+            if result(conclusion_table) { RowKind::Critical } else { RowKind::Counterexample }
+        })
+        .collect();
+
+    return Ok(Argument { tables, rows });
+}
+
+/**
+ * Counts the assignments that make the formula true without building its table. Rows are
+ * evaluated 64 at a time (see `bytecode::Program::evaluate_words`) across up to `threads`
+ * worker threads
+**/
+pub fn count_models(formula: &Formula, threads: usize) -> Result<u64, EvalError>
+{
+    let var_count = formula.variables.len();
+    if var_count > MAX_COUNTED_VARIABLES
+    {
+        return Err(EvalError::TooManyVariables { count: var_count, max: MAX_COUNTED_VARIABLES });
+    }
+
+    execution::validate_groups(&formula.groups, var_count)?;
+    let program = bytecode::compile(&formula.groups)?;
+    let program = &program;
+
+    let result = program.outputs.len() - 1;
+    let blocks: u64 = if var_count > 6 { 1 << (var_count - 6) } else { 1 };
+    let valid_rows: u64 = if var_count >= 6 { u64::MAX } else { (1 << (1 << var_count)) - 1 };
+
+    let count_range = move |first: u64, last: u64| -> u64 {
+        let mut values = vec![0u64; var_count];
+        let mut registers = vec![0u64; program.register_count];
+        let mut out_eval = vec![0u64; program.outputs.len()];
+        let mut count: u64 = 0;
+
+        for block in first..last
+        {
+            bytecode::load_block(&mut values, block);
+            program.evaluate_words(&values, &mut registers, &mut out_eval);
+            count += (out_eval[result] & valid_rows).count_ones() as u64;
+        }
+
+        return count;
+    };
+
+    let threads = (threads as u64).clamp(1, (blocks / MIN_BLOCKS_PER_THREAD).max(1));
+    if threads == 1
+    {
+        return Ok(count_range(0, blocks));
+    }
+
+    let chunk_size = blocks.div_ceil(threads);
+
+    let count = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|i| {
+                let first = i * chunk_size;
+                let last = (first + chunk_size).min(blocks);
+                scope.spawn(move || count_range(first, last))
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("Worker thread panicked"))
+            .sum()
+    });
+
+    return Ok(count);
+}
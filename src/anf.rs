@@ -0,0 +1,59 @@
+/**
+ * Coefficients of the algebraic normal form (Zhegalkin polynomial) of the function `truth` (see
+ * `table::truth_vector`), computed with the Möbius transform. `coefficients[m]` is set when the
+ * product of the variables set in minterm `m` is a term of the XOR, `coefficients[0]` being the
+ * constant 1. The transform is its own inverse
+**/
+pub fn mobius_transform(truth: &[bool]) -> Vec<bool>
+{
+    let mut coefficients = truth.to_vec();
+    let mut bit = 1;
+
+    // The coefficient of a product is the XOR of the function over every minterm below it,
+    // gathered one variable at a time
+    while bit < coefficients.len()
+    {
+        for minterm in (0..coefficients.len()).filter(|minterm| minterm & bit != 0)
+        {
+            coefficients[minterm] ^= coefficients[minterm ^ bit];
+        }
+
+        bit <<= 1;
+    }
+
+    return coefficients;
+}
+
+/// Largest number of variables in a single term, 0 for the constant functions
+pub fn degree(coefficients: &[bool]) -> u32
+{
+    return (0..coefficients.len())
+        .filter(|minterm| coefficients[*minterm])
+        .map(|minterm| minterm.count_ones())
+        .max()
+        .unwrap_or(0);
+}
+
+/**
+ * Renders the polynomial with `^` for XOR and `&` for AND, e.g. `1 ^ a ^ a&b`. Terms are sorted by
+ * degree and then by their variables in order, the zero function is `0`
+**/
+pub fn render(coefficients: &[bool], variables: &[String]) -> String
+{
+    let size = variables.len();
+    let term_variables = |minterm: usize| (0..size).filter(move |loc| minterm >> (size - loc - 1) & 1 == 1);
+
+    let mut terms = (0..coefficients.len()).filter(|minterm| coefficients[*minterm]).collect::<Vec<usize>>();
+    terms.sort_by_key(|minterm| (minterm.count_ones(), term_variables(*minterm).collect::<Vec<usize>>()));
+
+    if terms.is_empty() { return String::from("0"); }
+
+    return terms.iter()
+        .map(|minterm| match *minterm
+        {
+            0 => String::from("1"),
+            _ => term_variables(*minterm).map(|loc| variables[loc].as_str()).collect::<Vec<&str>>().join("&"),
+        })
+        .collect::<Vec<String>>()
+        .join(" ^ ");
+}
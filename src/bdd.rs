@@ -0,0 +1,569 @@
+use crate::compiler::{ASTNode, NodeOperation};
+use crate::scanner::OperatorType;
+use std::collections::HashMap;
+
+/// Index of a node in a `Bdd`, the two terminals come first
+pub type NodeId = usize;
+
+pub const FALSE: NodeId = 0;
+pub const TRUE: NodeId = 1;
+
+/// Default number of nodes a BDD may grow to before an operation gives up
+pub const DEFAULT_NODE_LIMIT: usize = 1_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BddError
+{
+    /// The BDD would have more than the given number of nodes
+    TooLarge { limit: usize },
+    /// A variable location outside of the order of the BDD
+    UnknownVariable { location: usize },
+}
+
+impl std::fmt::Display for BddError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self
+        {
+            BddError::TooLarge { limit } => write!(f, "BDD exceeds the limit of {} nodes", limit),
+            BddError::UnknownVariable { location } => write!(f, "Variable {} is not part of the BDD", location),
+        }
+    }
+}
+
+impl std::error::Error for BddError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Node
+{
+    /// Location of the variable the node tests, unused for the terminals
+    var: usize,
+    /// Successor when the variable is false
+    low: NodeId,
+    /// Successor when the variable is true
+    high: NodeId,
+}
+
+/// Operations whose results are kept in the apply cache
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Op
+{
+    Not,
+    Binary(OperatorType),
+}
+
+/**
+ * A reduced ordered binary decision diagram manager. Every node is unique (the unique table maps a
+ * variable and two successors to the node testing it), so two functions built in the same manager
+ * are equal exactly when they have the same `NodeId`. Variables are tested in the order given at
+ * construction, `order[0]` at the top
+**/
+pub struct Bdd
+{
+    nodes: Vec<Node>,
+    unique: HashMap<Node, NodeId>,
+    cache: HashMap<(Op, NodeId, NodeId), NodeId>,
+    order: Vec<usize>,
+    /// Position of every variable location in `order`
+    level_of: Vec<usize>,
+    limit: usize,
+}
+
+impl Bdd
+{
+    /**
+     * An empty manager testing the variable locations in `order` top to bottom. `order` must list
+     * every location below its length exactly once
+    **/
+    pub fn new(order: Vec<usize>, limit: usize) -> Self
+    {
+        let terminal = Node { var: usize::MAX, low: FALSE, high: FALSE };
+
+        let mut level_of = vec![0; order.len()];
+        for (level, var) in order.iter().enumerate()
+        {
+            level_of[*var] = level;
+        }
+
+        Self {
+            nodes: vec![terminal, terminal],
+            unique: HashMap::new(),
+            cache: HashMap::new(),
+            order,
+            level_of,
+            limit,
+        }
+    }
+
+    /// A manager for `var_count` variables tested in the order of their locations
+    pub fn with_natural_order(var_count: usize, limit: usize) -> Self
+    {
+        return Self::new((0..var_count).collect(), limit);
+    }
+
+    pub fn order(&self) -> &[usize]
+    {
+        return &self.order;
+    }
+
+    pub fn var_count(&self) -> usize
+    {
+        return self.order.len();
+    }
+
+    fn level(&self, node: NodeId) -> usize
+    {
+        if node <= TRUE { return self.order.len(); }
+        return self.level_of[self.nodes[node].var];
+    }
+
+    /// The node testing `var` with the given successors, shared with any existing equal node
+    fn make(&mut self, var: usize, low: NodeId, high: NodeId) -> Result<NodeId, BddError>
+    {
+        if low == high { return Ok(low); }
+
+        let node = Node { var, low, high };
+        if let Some(id) = self.unique.get(&node) { return Ok(*id); }
+
+        if self.nodes.len() >= self.limit
+        {
+            return Err(BddError::TooLarge { limit: self.limit });
+        }
+
+        self.nodes.push(node);
+        self.unique.insert(node, self.nodes.len() - 1);
+        return Ok(self.nodes.len() - 1);
+    }
+
+    pub fn constant(&self, val: bool) -> NodeId
+    {
+        return if val { TRUE } else { FALSE };
+    }
+
+    pub fn variable(&mut self, location: usize) -> Result<NodeId, BddError>
+    {
+        if location >= self.order.len()
+        {
+            return Err(BddError::UnknownVariable { location });
+        }
+
+        return self.make(location, FALSE, TRUE);
+    }
+
+    /// Successors of `node` for the variable at `level`, the node itself when it does not test it
+    fn cofactors(&self, node: NodeId, level: usize) -> (NodeId, NodeId)
+    {
+        if self.level(node) != level { return (node, node); }
+        return (self.nodes[node].low, self.nodes[node].high);
+    }
+
+    pub fn not(&mut self, f: NodeId) -> Result<NodeId, BddError>
+    {
+        if f <= TRUE { return Ok(f ^ 1); }
+        if let Some(result) = self.cache.get(&(Op::Not, f, f)) { return Ok(*result); }
+
+        let node = self.nodes[f];
+        let low = self.not(node.low)?;
+        let high = self.not(node.high)?;
+        let result = self.make(node.var, low, high)?;
+
+        self.cache.insert((Op::Not, f, f), result);
+        return Ok(result);
+    }
+
+    /// Result of a binary operation when it follows from the terminals or from `f == g` alone
+    fn terminal_case(&mut self, op: OperatorType, f: NodeId, g: NodeId) -> Result<Option<NodeId>, BddError>
+    {
+        let result = match op
+        {
+            OperatorType::AND if f == FALSE || g == FALSE => Some(FALSE),
+            OperatorType::AND if f == TRUE || f == g => Some(g),
+            OperatorType::AND if g == TRUE => Some(f),
+            OperatorType::OR if f == TRUE || g == TRUE => Some(TRUE),
+            OperatorType::OR if f == FALSE || f == g => Some(g),
+            OperatorType::OR if g == FALSE => Some(f),
+            OperatorType::CNDL if f == FALSE || g == TRUE || f == g => Some(TRUE),
+            OperatorType::CNDL if f == TRUE => Some(g),
+            OperatorType::CNDL if g == FALSE => Some(self.not(f)?),
+            OperatorType::BI_CNDL if f == g => Some(TRUE),
+            OperatorType::BI_CNDL if f == TRUE => Some(g),
+            OperatorType::BI_CNDL if g == TRUE => Some(f),
+            OperatorType::BI_CNDL if f == FALSE => Some(self.not(g)?),
+            OperatorType::BI_CNDL if g == FALSE => Some(self.not(f)?),
+            _ => None,
+        };
+
+        return Ok(result);
+    }
+
+    /// Combines two functions with a binary connective, splitting on the topmost variable of either
+    pub fn apply(&mut self, op: OperatorType, f: NodeId, g: NodeId) -> Result<NodeId, BddError>
+    {
+        if op == OperatorType::NOT { return self.not(f); }
+        if let Some(result) = self.terminal_case(op, f, g)? { return Ok(result); }
+
+        // AND, OR and <=> are commutative, share one cache entry for both operand orders
+        let key = match op
+        {
+            OperatorType::CNDL => (Op::Binary(op), f, g),
+            _ => (Op::Binary(op), f.min(g), f.max(g)),
+        };
+        if let Some(result) = self.cache.get(&key) { return Ok(*result); }
+
+        let level = self.level(f).min(self.level(g));
+        let (f_low, f_high) = self.cofactors(f, level);
+        let (g_low, g_high) = self.cofactors(g, level);
+
+        let low = self.apply(op, f_low, g_low)?;
+        let high = self.apply(op, f_high, g_high)?;
+        let result = self.make(self.order[level], low, high)?;
+
+        self.cache.insert(key, result);
+        return Ok(result);
+    }
+
+    /// If-then-else: `g` where `f` is true and `h` where it is false
+    pub fn ite(&mut self, f: NodeId, g: NodeId, h: NodeId) -> Result<NodeId, BddError>
+    {
+        let then = self.apply(OperatorType::AND, f, g)?;
+        let not_f = self.not(f)?;
+        let otherwise = self.apply(OperatorType::AND, not_f, h)?;
+
+        return self.apply(OperatorType::OR, then, otherwise);
+    }
+
+    /// Builds the function of a syntax tree whose variable locations are part of this manager
+    pub fn from_node(&mut self, node: &ASTNode) -> Result<NodeId, BddError>
+    {
+        let operand = |right: bool| {
+            let child = if right { &node.right } else { &node.left };
+            child.as_ref().expect("Operand not found")
+        };
+
+        return match node.op
+        {
+            NodeOperation::VariableDeref(loc) => self.variable(loc),
+            NodeOperation::Literal(val) => Ok(self.constant(val)),
+            NodeOperation::Subexpression | NodeOperation::IndexedSubexpression(_) => self.from_node(operand(false)),
+            NodeOperation::UnaryOperation(_) => {
+                let f = self.from_node(operand(false))?;
+                self.not(f)
+            },
+            NodeOperation::BinaryOperation(op) => {
+                let f = self.from_node(operand(false))?;
+                let g = self.from_node(operand(true))?;
+                self.apply(op, f, g)
+            },
+        };
+    }
+
+    /// The function `f` with variable `location` fixed to `value`
+    pub fn restrict(&mut self, f: NodeId, location: usize, value: bool) -> Result<NodeId, BddError>
+    {
+        if location >= self.order.len()
+        {
+            return Err(BddError::UnknownVariable { location });
+        }
+
+        let mut memo = HashMap::<NodeId, NodeId>::new();
+        return self.restrict_impl(f, location, value, &mut memo);
+    }
+
+    fn restrict_impl(&mut self, f: NodeId, location: usize, value: bool, memo: &mut HashMap<NodeId, NodeId>) -> Result<NodeId, BddError>
+    {
+        // Below the variable's level nothing tests it any more
+        if self.level(f) > self.level_of[location] { return Ok(f); }
+        if let Some(result) = memo.get(&f) { return Ok(*result); }
+
+        let node = self.nodes[f];
+        let result = if node.var == location
+        {
+            if value { node.high } else { node.low }
+        }
+        else
+        {
+            let low = self.restrict_impl(node.low, location, value, memo)?;
+            let high = self.restrict_impl(node.high, location, value, memo)?;
+            self.make(node.var, low, high)?
+        };
+
+        memo.insert(f, result);
+        return Ok(result);
+    }
+
+    /**
+     * An assignment of every variable (indexed by location) that makes `f` true, `None` when it is
+     * unsatisfiable. Variables the path does not test are set to false
+    **/
+    pub fn find_model(&self, f: NodeId) -> Option<Vec<bool>>
+    {
+        if f == FALSE { return None; }
+
+        let mut values = vec![false; self.order.len()];
+        let mut node = f;
+
+        // Every node other than FALSE has a path to TRUE, prefer the low branch where it has one
+        while node > TRUE
+        {
+            let Node { var, low, high } = self.nodes[node];
+            if low != FALSE
+            {
+                node = low;
+            }
+            else
+            {
+                values[var] = true;
+                node = high;
+            }
+        }
+
+        return Some(values);
+    }
+
+    /**
+     * Number of assignments of all the variables of the manager that make `f` true. Saturates at
+     * `u128::MAX`, which is only reached with more than 127 variables
+    **/
+    pub fn count_models(&self, f: NodeId) -> u128
+    {
+        let mut memo = HashMap::<NodeId, u128>::new();
+        let below = self.count_below(f, &mut memo);
+        return scale(below, self.level(f));
+    }
+
+    /// Models of `f` over the variables from its own level down
+    fn count_below(&self, f: NodeId, memo: &mut HashMap<NodeId, u128>) -> u128
+    {
+        if f <= TRUE { return f as u128; }
+        if let Some(count) = memo.get(&f) { return *count; }
+
+        let Node { low, high, .. } = self.nodes[f];
+        let level = self.level(f);
+
+        let low_count = scale(self.count_below(low, memo), self.level(low) - level - 1);
+        let high_count = scale(self.count_below(high, memo), self.level(high) - level - 1);
+        let count = low_count.saturating_add(high_count);
+
+        memo.insert(f, count);
+        return count;
+    }
+
+    /// Nodes reachable from `f`, terminals included, in depth-first order
+    pub fn reachable(&self, f: NodeId) -> Vec<NodeId>
+    {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut stack = vec![f];
+        let mut reached = Vec::<NodeId>::new();
+
+        while let Some(node) = stack.pop()
+        {
+            if seen[node] { continue; }
+            seen[node] = true;
+            reached.push(node);
+
+            if node > TRUE
+            {
+                stack.push(self.nodes[node].high);
+                stack.push(self.nodes[node].low);
+            }
+        }
+
+        return reached;
+    }
+
+    /// Variables `f` depends on, indexed by location. A reduced BDD only tests those variables
+    pub fn support(&self, f: NodeId) -> Vec<bool>
+    {
+        let mut support = vec![false; self.order.len()];
+
+        for node in self.reachable(f).into_iter().filter(|node| *node > TRUE)
+        {
+            support[self.nodes[node].var] = true;
+        }
+
+        return support;
+    }
+
+    /// Number of nodes reachable from `f`, terminals included
+    pub fn size(&self, f: NodeId) -> usize
+    {
+        return self.reachable(f).len();
+    }
+
+    /// Rebuilds `f` in another manager, which may test the variables in a different order
+    pub fn transfer(&self, f: NodeId, target: &mut Bdd) -> Result<NodeId, BddError>
+    {
+        let mut memo = HashMap::<NodeId, NodeId>::new();
+        return self.transfer_impl(f, target, &mut memo);
+    }
+
+    fn transfer_impl(&self, f: NodeId, target: &mut Bdd, memo: &mut HashMap<NodeId, NodeId>) -> Result<NodeId, BddError>
+    {
+        if f <= TRUE { return Ok(f); }
+        if let Some(result) = memo.get(&f) { return Ok(*result); }
+
+        let node = self.nodes[f];
+        let low = self.transfer_impl(node.low, target, memo)?;
+        let high = self.transfer_impl(node.high, target, memo)?;
+        let var = target.variable(node.var)?;
+        let result = target.ite(var, high, low)?;
+
+        memo.insert(f, result);
+        return Ok(result);
+    }
+
+    /**
+     * Swaps the variables at `level` and `level + 1` in place. Nodes testing the upper variable
+     * that have a successor testing the lower one are rewritten to test the lower variable first,
+     * so every `NodeId` keeps its function and the nodes above the two levels are left alone.
+     * Nodes that are no longer reachable stay in the manager until it is transferred
+    **/
+    fn swap_levels(&mut self, level: usize) -> Result<(), BddError>
+    {
+        let (upper, lower) = (self.order[level], self.order[level + 1]);
+        let rewritten = (TRUE + 1..self.nodes.len()).filter(|node| self.nodes[*node].var == upper).collect::<Vec<NodeId>>();
+
+        self.order.swap(level, level + 1);
+        self.level_of[upper] = level + 1;
+        self.level_of[lower] = level;
+
+        for node in rewritten
+        {
+            let Node { low, high, .. } = self.nodes[node];
+            let split = |child: NodeId| match child > TRUE && self.nodes[child].var == lower
+            {
+                true => (self.nodes[child].low, self.nodes[child].high),
+                false => (child, child),
+            };
+
+            let ((low_low, low_high), (high_low, high_high)) = (split(low), split(high));
+            if (low_low, high_low) == (low_high, high_high) { continue; }
+
+            // Neither successor tests `lower`, so an existing node it matches is never rewritten
+            let new_low = self.make(upper, low_low, high_low)?;
+            let new_high = self.make(upper, low_high, high_high)?;
+
+            self.unique.remove(&self.nodes[node]);
+            self.nodes[node] = Node { var: lower, low: new_low, high: new_high };
+            self.unique.insert(self.nodes[node], node);
+        }
+
+        return Ok(());
+    }
+
+    /**
+     * Moves `var` through every level by swapping it with its neighbours, first to the bottom and
+     * then to the top, and leaves it at the level where `f` had the fewest nodes
+    **/
+    fn sift_variable(&mut self, f: NodeId, var: usize) -> Result<(), BddError>
+    {
+        let mut level = self.level_of[var];
+        let (mut best_size, mut best_level) = (self.size(f), level);
+
+        let mut visit = |bdd: &Bdd, level: usize| {
+            let size = bdd.size(f);
+            if size < best_size { (best_size, best_level) = (size, level); }
+        };
+
+        while level + 1 < self.order.len()
+        {
+            self.swap_levels(level)?;
+            level += 1;
+            visit(self, level);
+        }
+
+        while level > 0
+        {
+            self.swap_levels(level - 1)?;
+            level -= 1;
+            visit(self, level);
+        }
+
+        while level < best_level
+        {
+            self.swap_levels(level)?;
+            level += 1;
+        }
+
+        return Ok(());
+    }
+
+    /**
+     * Looks for a variable order giving a smaller BDD for `f` by sifting: every variable in turn,
+     * those at the widest levels first, is moved through every position by swapping adjacent
+     * levels in place and left where the BDD was smallest. Returns a new manager with the best
+     * order found and `f` in it. A variable whose moves exceed the node limit stays where it was
+    **/
+    pub fn sift(&self, f: NodeId) -> Result<(Bdd, NodeId), BddError>
+    {
+        let mut best = Bdd::new(self.order.clone(), self.limit);
+        let mut best_root = self.transfer(f, &mut best)?;
+
+        let mut width = vec![0; self.order.len()];
+        for node in self.reachable(f).into_iter().filter(|node| *node > TRUE)
+        {
+            width[self.nodes[node].var] += 1;
+        }
+
+        let mut vars = self.order.clone();
+        vars.sort_by_key(|var| std::cmp::Reverse(width[*var]));
+
+        for var in vars
+        {
+            // Swaps leave unreachable nodes behind, the copy is transferred once the variable is
+            // placed so they do not pile up
+            let mut work = Bdd::new(best.order.clone(), self.limit);
+            let root = best.transfer(best_root, &mut work)?;
+            if work.sift_variable(root, var).is_err() { continue; }
+
+            let mut compact = Bdd::new(work.order.clone(), self.limit);
+            best_root = work.transfer(root, &mut compact)?;
+            best = compact;
+        }
+
+        return Ok((best, best_root));
+    }
+
+    /**
+     * Graphviz DOT description of the BDD rooted at `f`. Dashed edges are taken when the variable
+     * is false, solid edges when it is true
+    **/
+    pub fn to_dot(&self, f: NodeId, variables: &[String]) -> String
+    {
+        let mut lines = vec![String::from("digraph bdd {")];
+        let mut nodes = self.reachable(f);
+        nodes.sort_by_key(|node| (self.level(*node), *node));
+
+        for node in &nodes
+        {
+            match *node
+            {
+                FALSE => lines.push(String::from("    n0 [label=\"F\", shape=box];")),
+                TRUE => lines.push(String::from("    n1 [label=\"T\", shape=box];")),
+                _ => {
+                    let Node { var, low, high } = self.nodes[*node];
+                    lines.push(format!("    n{} [label=\"{}\", shape=circle];", node, variables[var]));
+                    lines.push(format!("    n{} -> n{} [style=dashed];", node, low));
+                    lines.push(format!("    n{} -> n{};", node, high));
+                },
+            }
+        }
+
+        lines.push(String::from("}"));
+        lines.push(String::new());
+        return lines.join("\n");
+    }
+}
+
+/// `count` times 2 to the power of `exponent`, saturating
+fn scale(count: u128, exponent: usize) -> u128
+{
+    if count == 0 { return 0; }
+
+    return match 1u128.checked_shl(exponent as u32)
+    {
+        Some(factor) if exponent < 128 => count.saturating_mul(factor),
+        _ => u128::MAX,
+    };
+}
@@ -0,0 +1,399 @@
+use crate::compiler::NodeOperation;
+use crate::execution::EvalError;
+use crate::scanner::OperatorType;
+use std::collections::HashMap;
+
+/// Bits of a 64 row block in which minterm bit `i` (for `i < 6`) is set
+const LANE_MASKS: [u64; 6] = [
+    0xAAAA_AAAA_AAAA_AAAA,
+    0xCCCC_CCCC_CCCC_CCCC,
+    0xF0F0_F0F0_F0F0_F0F0,
+    0xFF00_FF00_FF00_FF00,
+    0xFFFF_0000_FFFF_0000,
+    0xFFFF_FFFF_0000_0000,
+];
+
+/**
+ * Fills `values` with the inputs of rows `64 * block` to `64 * block + 63` for
+ * `Program::evaluate_words`, the first variable is the most significant bit of the minterm
+**/
+pub fn load_block(values: &mut [u64], block: u64)
+{
+    let var_count = values.len();
+    for (col, value) in values.iter_mut().enumerate()
+    {
+        let bit = var_count - col - 1;
+        *value = if bit < 6 { LANE_MASKS[bit] }
+                 else if (block >> (bit - 6)) & 1 == 1 { u64::MAX }
+                 else { 0 };
+    }
+}
+
+/// A value an instruction can read
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Operand
+{
+    Const(bool),
+    Input(usize),
+    Register(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instruction
+{
+    Not { dst: usize, src: Operand },
+    Binary { op: OperatorType, dst: usize, left: Operand, right: Operand },
+}
+
+/**
+ * Subexpression groups lowered to straight-line register code. Every intermediate value
+ * gets its own register, so evaluating a row is a single pass over `instructions` with no
+ * stack and no allocation
+**/
+#[derive(Debug)]
+pub struct Program
+{
+    pub instructions: Vec<Instruction>,
+    /// Where the value of each subexpression group ends up
+    pub outputs: Vec<Operand>,
+    pub register_count: usize,
+    /// Number of variable values a row must provide
+    pub input_count: usize,
+}
+
+struct Lowering
+{
+    instructions: Vec<Instruction>,
+    register_count: usize,
+    /// Already emitted instructions keyed by (operator, operands), used to reuse common subexpressions
+    emitted: HashMap<(Option<OperatorType>, Operand, Operand), Operand>,
+    /// Maps the result of every emitted negation back to the value that was negated
+    negated: HashMap<Operand, Operand>,
+}
+
+impl Lowering
+{
+    fn emit(&mut self, op: Option<OperatorType>, left: Operand, right: Operand) -> Operand
+    {
+        if let Some(existing) = self.emitted.get(&(op, left, right))
+        {
+            return *existing;
+        }
+
+        let dst = self.register_count;
+        self.register_count += 1;
+
+        self.instructions.push(match op
+        {
+            Some(op) => Instruction::Binary { op, dst, left, right },
+            None => Instruction::Not { dst, src: left },
+        });
+
+        let result = Operand::Register(dst);
+        self.emitted.insert((op, left, right), result);
+        return result;
+    }
+
+    fn not(&mut self, src: Operand) -> Operand
+    {
+        if let Operand::Const(val) = src
+        {
+            return Operand::Const(!val);
+        }
+
+        // Double negation, reuse the value that was negated
+        if let Some(inner) = self.negated.get(&src)
+        {
+            return *inner;
+        }
+
+        let result = self.emit(None, src, src);
+        self.negated.insert(result, src);
+        return result;
+    }
+
+    fn binary(&mut self, op: OperatorType, left: Operand, right: Operand) -> Operand
+    {
+        // Constant folding, including the identities where only one side is known
+        match (op, left, right)
+        {
+            (OperatorType::AND, Operand::Const(a), Operand::Const(b)) => return Operand::Const(a && b),
+            (OperatorType::AND, Operand::Const(true), x) | (OperatorType::AND, x, Operand::Const(true)) => return x,
+            (OperatorType::AND, Operand::Const(false), _) | (OperatorType::AND, _, Operand::Const(false)) => return Operand::Const(false),
+
+            (OperatorType::OR, Operand::Const(a), Operand::Const(b)) => return Operand::Const(a || b),
+            (OperatorType::OR, Operand::Const(false), x) | (OperatorType::OR, x, Operand::Const(false)) => return x,
+            (OperatorType::OR, Operand::Const(true), _) | (OperatorType::OR, _, Operand::Const(true)) => return Operand::Const(true),
+
+            (OperatorType::CNDL, Operand::Const(false), _) | (OperatorType::CNDL, _, Operand::Const(true)) => return Operand::Const(true),
+            (OperatorType::CNDL, Operand::Const(true), x) => return x,
+            (OperatorType::CNDL, x, Operand::Const(false)) => return self.not(x),
+
+            (OperatorType::BI_CNDL, Operand::Const(a), Operand::Const(b)) => return Operand::Const(a == b),
+            (OperatorType::BI_CNDL, Operand::Const(true), x) | (OperatorType::BI_CNDL, x, Operand::Const(true)) => return x,
+            (OperatorType::BI_CNDL, Operand::Const(false), x) | (OperatorType::BI_CNDL, x, Operand::Const(false)) => return self.not(x),
+
+            _ => ()
+        }
+
+        if left == right
+        {
+            return match op
+            {
+                OperatorType::AND | OperatorType::OR => left,
+                _ => Operand::Const(true),
+            };
+        }
+
+        // Order the operands of commutative operators so `p & q` and `q & p` share a register
+        let (left, right) = match op
+        {
+            OperatorType::AND | OperatorType::OR | OperatorType::BI_CNDL if right < left => (right, left),
+            _ => (left, right),
+        };
+
+        return self.emit(Some(op), left, right);
+    }
+}
+
+/**
+ * Removes the instructions no output depends on, such as a negation that a later double
+ * negation or constant folded away, and renumbers the remaining registers in order
+**/
+fn remove_dead_instructions(instructions: Vec<Instruction>, outputs: &mut [Operand], register_count: usize) -> (Vec<Instruction>, usize)
+{
+    let mut live = vec![false; register_count];
+    let mark = |operand: Operand, live: &mut Vec<bool>| {
+        if let Operand::Register(reg) = operand { live[reg] = true; }
+    };
+
+    for output in outputs.iter()
+    {
+        mark(*output, &mut live);
+    }
+
+    for inst in instructions.iter().rev()
+    {
+        match *inst
+        {
+            Instruction::Not { dst, src } if live[dst] => mark(src, &mut live),
+            Instruction::Binary { dst, left, right, .. } if live[dst] => {
+                mark(left, &mut live);
+                mark(right, &mut live);
+            },
+            _ => (),
+        }
+    }
+
+    let mut renumbered = vec![0; register_count];
+    let mut count = 0;
+    for (reg, live) in live.iter().enumerate()
+    {
+        if *live
+        {
+            renumbered[reg] = count;
+            count += 1;
+        }
+    }
+
+    let rename = |operand: Operand| match operand
+    {
+        Operand::Register(reg) => Operand::Register(renumbered[reg]),
+        _ => operand,
+    };
+
+    for output in outputs.iter_mut()
+    {
+        *output = rename(*output);
+    }
+
+    let instructions = instructions.into_iter()
+        .filter_map(|inst| match inst
+        {
+            Instruction::Not { dst, src } if live[dst] => Some(Instruction::Not { dst: renumbered[dst], src: rename(src) }),
+            Instruction::Binary { op, dst, left, right } if live[dst] => {
+                Some(Instruction::Binary { op, dst: renumbered[dst], left: rename(left), right: rename(right) })
+            },
+            _ => None,
+        })
+        .collect();
+
+    return (instructions, count);
+}
+
+/**
+ * Lowers the output of `execution::subexpression_groups` to a `Program`. Operations with
+ * constant operands are folded away, identical operations (across all groups) are
+ * computed only once and instructions whose result ends up unused are dropped
+**/
+pub fn compile(groups: &[Vec<NodeOperation>]) -> Result<Program, EvalError>
+{
+    let mut lowering = Lowering {
+        instructions: vec![],
+        register_count: 0,
+        emitted: HashMap::new(),
+        negated: HashMap::new(),
+    };
+
+    let mut outputs = Vec::<Operand>::with_capacity(groups.len());
+    let mut input_count: usize = 0;
+    let mut operands_stack = Vec::<Operand>::with_capacity(100);
+
+    for (index, grp) in groups.iter().enumerate()
+    {
+        operands_stack.clear();
+
+        for op in grp
+        {
+            match op
+            {
+                NodeOperation::Literal(val) => { operands_stack.push(Operand::Const(*val)); },
+                NodeOperation::VariableDeref(loc) => {
+                    input_count = input_count.max(*loc + 1);
+                    operands_stack.push(Operand::Input(*loc));
+                },
+                NodeOperation::IndexedSubexpression(sub_loc) => {
+                    let output = outputs.get(*sub_loc as usize)
+                        .ok_or(EvalError::UnresolvedSubexpression { group: index, index: *sub_loc })?;
+                    operands_stack.push(*output);
+                },
+                NodeOperation::BinaryOperation(op_type) => {
+                    let right = operands_stack.pop().ok_or(EvalError::MissingOperand { group: index })?;
+                    let left = operands_stack.pop().ok_or(EvalError::MissingOperand { group: index })?;
+
+                    let result = match *op_type
+                    {
+                        OperatorType::NOT => { return Err(EvalError::UnsupportedOperation { group: index, op: *op }); }
+                        _ => lowering.binary(*op_type, left, right)
+                    };
+                    operands_stack.push(result);
+                },
+                NodeOperation::UnaryOperation(op_type) => {
+                    let left = operands_stack.pop().ok_or(EvalError::MissingOperand { group: index })?;
+
+                    let result = match *op_type
+                    {
+                        OperatorType::NOT => lowering.not(left),
+                        _ => { return Err(EvalError::UnsupportedOperation { group: index, op: *op }); }
+                    };
+
+                    operands_stack.push(result);
+                },
+                _ => ()
+            }
+        }
+
+        let output = operands_stack.pop().ok_or(EvalError::UnbalancedGroup { group: index })?;
+        if !operands_stack.is_empty()
+        {
+            return Err(EvalError::UnbalancedGroup { group: index });
+        }
+
+        outputs.push(output);
+    }
+
+    let (instructions, register_count) = remove_dead_instructions(lowering.instructions, &mut outputs, lowering.register_count);
+
+    return Ok(Program {
+        instructions,
+        outputs,
+        register_count,
+        input_count,
+    });
+}
+
+impl Program
+{
+    #[inline]
+    fn read(operand: Operand, values: &[bool], registers: &[bool]) -> bool
+    {
+        match operand
+        {
+            Operand::Const(val) => val,
+            Operand::Input(loc) => values[loc],
+            Operand::Register(reg) => registers[reg],
+        }
+    }
+
+    /**
+     * Same contract as `execution::evaluate`, except that the buffers are not checked on every
+     * row: `values` must have at least `input_count` entries, `registers` at least `register_count`
+     * (it is scratch space and can be reused across calls) and `out_eval` one per output
+    **/
+    pub fn evaluate(&self, values: &[bool], registers: &mut [bool], out_eval: &mut [bool])
+    {
+        for inst in &self.instructions
+        {
+            match *inst
+            {
+                Instruction::Not { dst, src } => {
+                    registers[dst] = !Self::read(src, values, registers);
+                },
+                Instruction::Binary { op, dst, left, right } => {
+                    let left = Self::read(left, values, registers);
+                    let right = Self::read(right, values, registers);
+
+                    registers[dst] = match op
+                    {
+                        OperatorType::AND => left && right,
+                        OperatorType::OR => left || right,
+                        OperatorType::CNDL => !left || right,
+                        OperatorType::BI_CNDL => left == right,
+                        OperatorType::NOT => unreachable!("compile() rejects binary NOT"),
+                    };
+                },
+            }
+        }
+
+        for (index, output) in self.outputs.iter().enumerate()
+        {
+            out_eval[index] = Self::read(*output, values, registers);
+        }
+    }
+
+    #[inline]
+    fn read_word(operand: Operand, values: &[u64], registers: &[u64]) -> u64
+    {
+        match operand
+        {
+            Operand::Const(val) => if val { u64::MAX } else { 0 },
+            Operand::Input(loc) => values[loc],
+            Operand::Register(reg) => registers[reg],
+        }
+    }
+
+    /**
+     * Bit-parallel version of `evaluate`: bit `i` of every word belongs to the `i`-th of 64
+     * independent rows, so one pass evaluates 64 assignments at once
+    **/
+    pub fn evaluate_words(&self, values: &[u64], registers: &mut [u64], out_eval: &mut [u64])
+    {
+        for inst in &self.instructions
+        {
+            match *inst
+            {
+                Instruction::Not { dst, src } => {
+                    registers[dst] = !Self::read_word(src, values, registers);
+                },
+                Instruction::Binary { op, dst, left, right } => {
+                    let left = Self::read_word(left, values, registers);
+                    let right = Self::read_word(right, values, registers);
+
+                    registers[dst] = match op
+                    {
+                        OperatorType::AND => left & right,
+                        OperatorType::OR => left | right,
+                        OperatorType::CNDL => !left | right,
+                        OperatorType::BI_CNDL => !(left ^ right),
+                        OperatorType::NOT => unreachable!("compile() rejects binary NOT"),
+                    };
+                },
+            }
+        }
+
+        for (index, output) in self.outputs.iter().enumerate()
+        {
+            out_eval[index] = Self::read_word(*output, values, registers);
+        }
+    }
+}
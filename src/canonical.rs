@@ -0,0 +1,118 @@
+use crate::execution::{SYMBOL_AND, SYMBOL_LEFT_PAREN, SYMBOL_NOT, SYMBOL_OR, SYMBOL_RIGHT_PAREN};
+
+/// A product or sum of literals, given as (variable location, positive) pairs
+pub type Term = Vec<(usize, bool)>;
+
+// Constants are spelled as literals the scanner accepts so the output can be parsed again
+const LITERAL_TRUE: &str = "TRUE";
+const LITERAL_FALSE: &str = "FALSE";
+
+/// Indices of the rows of a truth vector (see `table::truth_vector`) where the function is true
+pub fn minterms(truth: &[bool]) -> Vec<u32>
+{
+    return (0..truth.len() as u32).filter(|index| truth[*index as usize]).collect();
+}
+
+/// Indices of the rows of a truth vector where the function is false
+pub fn maxterms(truth: &[bool]) -> Vec<u32>
+{
+    return (0..truth.len() as u32).filter(|index| !truth[*index as usize]).collect();
+}
+
+/// Renders `variables[loc]` or its negation
+pub fn render_literal(variables: &[String], loc: usize, positive: bool) -> String
+{
+    if positive
+    {
+        return variables[loc].clone();
+    }
+
+    return [SYMBOL_NOT, variables[loc].as_str()].join("");
+}
+
+/**
+ * Joins terms with `separator`, wrapping terms of more than one literal in parentheses. With no
+ * terms the result is `empty`
+**/
+fn render_terms(terms: &[Vec<String>], inner: &str, separator: &str, empty: &str) -> String
+{
+    if terms.is_empty()
+    {
+        return String::from(empty);
+    }
+
+    return terms.iter()
+        .map(|literals| match literals.len()
+        {
+            0 => String::from(if empty == LITERAL_FALSE { LITERAL_TRUE } else { LITERAL_FALSE }),
+            1 => literals[0].clone(),
+            _ => [SYMBOL_LEFT_PAREN, &literals.join(inner), SYMBOL_RIGHT_PAREN].join(""),
+        })
+        .collect::<Vec<String>>()
+        .join(separator);
+}
+
+/// Renders a sum of products, each product given as its (variable location, positive) literals
+pub fn render_sum_of_products(variables: &[String], products: &[Term]) -> String
+{
+    let terms = products.iter()
+        .map(|product| product.iter().map(|(loc, positive)| render_literal(variables, *loc, *positive)).collect())
+        .collect::<Vec<Vec<String>>>();
+
+    return render_terms(&terms, SYMBOL_AND, SYMBOL_OR, LITERAL_FALSE);
+}
+
+/// Renders a product of sums, each sum given as its (variable location, positive) literals
+pub fn render_product_of_sums(variables: &[String], sums: &[Term]) -> String
+{
+    let terms = sums.iter()
+        .map(|sum| sum.iter().map(|(loc, positive)| render_literal(variables, *loc, *positive)).collect())
+        .collect::<Vec<Vec<String>>>();
+
+    return render_terms(&terms, SYMBOL_OR, SYMBOL_AND, LITERAL_TRUE);
+}
+
+/// The literals of the minterm (or, with every literal negated, the maxterm) at `index`
+fn term_literals(var_count: usize, index: u32, negate: bool) -> Term
+{
+    return (0..var_count)
+        .map(|loc| (loc, ((index >> (var_count - loc - 1)) & 1 == 1) != negate))
+        .collect();
+}
+
+/// Canonical sum of products: one product of all variables per true row
+pub fn canonical_dnf(variables: &[String], truth: &[bool]) -> String
+{
+    let products = minterms(truth).into_iter()
+        .map(|index| term_literals(variables.len(), index, false))
+        .collect::<Vec<Term>>();
+
+    return render_sum_of_products(variables, &products);
+}
+
+/// Canonical product of sums: one sum of all variables per false row
+pub fn canonical_cnf(variables: &[String], truth: &[bool]) -> String
+{
+    let sums = maxterms(truth).into_iter()
+        .map(|index| term_literals(variables.len(), index, true))
+        .collect::<Vec<Term>>();
+
+    return render_product_of_sums(variables, &sums);
+}
+
+fn index_list(indices: &[u32]) -> String
+{
+    return indices.iter().map(|index| index.to_string()).collect::<Vec<String>>().join(", ");
+}
+
+/// Minterm index notation, e.g. `Σm(1, 3)`
+pub fn minterm_notation(truth: &[bool]) -> String
+{
+    return format!("Σm({})", index_list(&minterms(truth)));
+}
+
+/// Maxterm index notation, e.g. `ΠM(0, 2)`
+pub fn maxterm_notation(truth: &[bool]) -> String
+{
+    return format!("ΠM({})", index_list(&maxterms(truth)));
+}
@@ -0,0 +1,346 @@
+use crate::canonical::{self, Term};
+use crate::compiler::{ASTNode, NodeOperation};
+use crate::execution::{SYMBOL_AND, SYMBOL_LEFT_PAREN, SYMBOL_RIGHT_PAREN};
+use crate::scanner::{self, OperatorType, TokenType};
+use std::collections::HashSet;
+
+/// A literal in DIMACS convention: variable `v` (counting from 1) is `v`, its negation is `-v`
+pub type Lit = i32;
+
+/// A formula in conjunctive normal form, a conjunction of clauses that are disjunctions of literals
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Cnf
+{
+    pub num_vars: usize,
+    pub clauses: Vec<Vec<Lit>>,
+}
+
+/**
+ * Tseitin encoding of syntax trees into an equisatisfiable CNF. Variable `i` of the formula
+ * (its location in `CompiledSyntaxBTree.variables`) becomes CNF variable `i + 1`, every
+ * operator gets a fresh variable constrained to be equivalent to its result
+**/
+pub struct TseitinEncoder
+{
+    pub cnf: Cnf,
+    /// Variable fixed to true, created on the first literal `TRUE` or `FALSE`
+    constant: Option<Lit>,
+}
+
+impl TseitinEncoder
+{
+    /// Reserves CNF variables `1..=var_count` for the variables of the formula
+    pub fn new(var_count: usize) -> Self
+    {
+        Self {
+            cnf: Cnf { num_vars: var_count, clauses: vec![] },
+            constant: None,
+        }
+    }
+
+    fn fresh(&mut self) -> Lit
+    {
+        self.cnf.num_vars += 1;
+        return self.cnf.num_vars as Lit;
+    }
+
+    fn constant(&mut self, val: bool) -> Lit
+    {
+        let lit = match self.constant
+        {
+            Some(lit) => lit,
+            None => {
+                let lit = self.fresh();
+                self.cnf.clauses.push(vec![lit]);
+                self.constant = Some(lit);
+                lit
+            }
+        };
+
+        return if val { lit } else { -lit };
+    }
+
+    /// Adds clauses making `out` equivalent to `op(left, right)`
+    fn define_binary(&mut self, op: OperatorType, out: Lit, left: Lit, right: Lit)
+    {
+        let clauses: Vec<Vec<Lit>> = match op
+        {
+            OperatorType::AND => vec![
+                vec![-out, left],
+                vec![-out, right],
+                vec![out, -left, -right],
+            ],
+            OperatorType::OR => vec![
+                vec![out, -left],
+                vec![out, -right],
+                vec![-out, left, right],
+            ],
+            OperatorType::CNDL => vec![
+                vec![out, left],
+                vec![out, -right],
+                vec![-out, -left, right],
+            ],
+            OperatorType::BI_CNDL => vec![
+                vec![-out, -left, right],
+                vec![-out, left, -right],
+                vec![out, left, right],
+                vec![out, -left, -right],
+            ],
+            OperatorType::NOT => unreachable!("NOT is not a binary operator"),
+        };
+
+        self.cnf.clauses.extend(clauses);
+    }
+
+    /**
+     * Encodes `node` and returns a literal that is true exactly when `node` is. Nothing is
+     * asserted about the result, see `assert`
+    **/
+    pub fn encode(&mut self, node: &ASTNode) -> Lit
+    {
+        match node.op
+        {
+            NodeOperation::VariableDeref(loc) => (loc + 1) as Lit,
+            NodeOperation::Literal(val) => self.constant(val),
+            NodeOperation::UnaryOperation(_) => -self.encode(node.left.as_ref().unwrap()),
+            NodeOperation::BinaryOperation(op) => {
+                let left = self.encode(node.left.as_ref().unwrap());
+                let right = self.encode(node.right.as_ref().unwrap());
+                let out = self.fresh();
+                self.define_binary(op, out, left, right);
+                out
+            },
+            NodeOperation::Subexpression | NodeOperation::IndexedSubexpression(_) => {
+                self.encode(node.left.as_ref().unwrap())
+            },
+        }
+    }
+
+    /// Adds a unit clause forcing `lit` to be true
+    pub fn assert(&mut self, lit: Lit)
+    {
+        self.cnf.clauses.push(vec![lit]);
+    }
+}
+
+/**
+ * Returns a CNF that is satisfiable exactly when the formula rooted at `node` is. The first
+ * `var_count` variables of every model are an assignment of the formula's variables making it true.
+ * A constant formula has no clauses when it is `TRUE` and a single empty clause when it is `FALSE`
+**/
+pub fn tseitin(node: &ASTNode, var_count: usize) -> Cnf
+{
+    let mut root = node;
+    while let NodeOperation::Subexpression | NodeOperation::IndexedSubexpression(_) = root.op
+    {
+        root = root.left.as_ref().unwrap();
+    }
+
+    if let NodeOperation::Literal(val) = root.op
+    {
+        let clauses = if val { vec![] } else { vec![vec![]] };
+        return Cnf { num_vars: var_count, clauses };
+    }
+
+    let mut encoder = TseitinEncoder::new(var_count);
+    let root = encoder.encode(node);
+    encoder.assert(root);
+
+    return encoder.cnf;
+}
+
+/**
+ * Writes `cnf` in DIMACS format. The first `variables.len()` CNF variables are taken to be the
+ * variables of the encoded formula, and a `c <number> <name>` comment line maps each of them
+ * back to its name. Remaining variables are auxiliary and left unnamed
+**/
+pub fn to_dimacs(cnf: &Cnf, variables: &[String]) -> String
+{
+    let mut lines = Vec::<String>::with_capacity(cnf.clauses.len() + variables.len() + 2);
+
+    lines.push(String::from("c generated by ttbl"));
+    for (loc, name) in variables.iter().enumerate()
+    {
+        lines.push(format!("c {} {}", loc + 1, name));
+    }
+
+    lines.push(format!("p cnf {} {}", cnf.num_vars, cnf.clauses.len()));
+
+    for clause in &cnf.clauses
+    {
+        let mut line = clause.iter().map(|lit| lit.to_string()).collect::<Vec<String>>();
+        line.push(String::from("0"));
+        lines.push(line.join(" "));
+    }
+
+    lines.push(String::new());
+    return lines.join("\n");
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DimacsError
+{
+    /// Line of the input the error was found on, counting from 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for DimacsError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        write!(f, "DIMACS error on line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for DimacsError {}
+
+/// Whether `name` would be scanned as a single variable token
+fn is_variable_name(name: &str) -> bool
+{
+    let tokens = scanner::tokenize(name);
+    return tokens.len() == 2 && tokens[0].token_type == TokenType::Variable && tokens[0].lexeme == name;
+}
+
+/**
+ * Reads a DIMACS CNF file. Returns the CNF and a name for each of its variables, given by
+ * `c <number> <name>` comment lines (as written by `to_dimacs`) or `x<number>` otherwise. A comment
+ * name that is not a valid variable name, or that an earlier comment already gave to another
+ * variable, is ignored. A default name another variable already has gets a letter suffix, `x5a`
+**/
+pub fn parse_dimacs(text: &str) -> Result<(Cnf, Vec<String>), DimacsError>
+{
+    let mut cnf: Option<Cnf> = None;
+    let mut mapping = Vec::<(usize, String)>::new();
+    let mut clause = Vec::<Lit>::new();
+
+    for (index, line) in text.lines().enumerate()
+    {
+        let error = |message: String| DimacsError { line: index + 1, message };
+        let line = line.trim();
+
+        if line.is_empty() { continue; }
+
+        // Some benchmark collections end their files with a `%` line
+        if line.starts_with('%') { break; }
+
+        if let Some(comment) = line.strip_prefix('c')
+        {
+            let words = comment.split_whitespace().collect::<Vec<&str>>();
+            if let [number, name] = words[..]
+            {
+                if let Ok(number) = number.parse::<usize>() { mapping.push((number, String::from(name))); }
+            }
+            continue;
+        }
+
+        if line.starts_with('p')
+        {
+            if cnf.is_some() { return Err(error(String::from("Duplicate problem line"))); }
+
+            let words = line.split_whitespace().collect::<Vec<&str>>();
+            let num_vars = match words[..]
+            {
+                ["p", "cnf", num_vars, num_clauses] if num_clauses.parse::<usize>().is_ok() => num_vars.parse::<usize>().ok(),
+                _ => None,
+            };
+
+            let num_vars = num_vars.ok_or_else(|| error(String::from("Expected \"p cnf <variables> <clauses>\"")))?;
+            cnf = Some(Cnf { num_vars, clauses: vec![] });
+            continue;
+        }
+
+        let cnf = cnf.as_mut().ok_or_else(|| error(String::from("Clause before the problem line")))?;
+
+        for word in line.split_whitespace()
+        {
+            let lit = word.parse::<Lit>().map_err(|_| error(format!("Invalid literal \"{}\"", word)))?;
+
+            if lit == 0
+            {
+                cnf.clauses.push(std::mem::take(&mut clause));
+            }
+            else if lit.unsigned_abs() as usize > cnf.num_vars
+            {
+                return Err(error(format!("Literal {} exceeds the {} declared variables", lit, cnf.num_vars)));
+            }
+            else
+            {
+                clause.push(lit);
+            }
+        }
+    }
+
+    let mut cnf = cnf.ok_or(DimacsError { line: 0, message: String::from("Missing problem line") })?;
+
+    // The terminating 0 of the last clause is optional
+    if !clause.is_empty() { cnf.clauses.push(clause); }
+
+    let mut named = vec![None::<String>; cnf.num_vars];
+    let mut taken = HashSet::<String>::new();
+
+    for (number, name) in &mapping
+    {
+        let Some(slot) = number.checked_sub(1).and_then(|index| named.get_mut(index)) else { continue; };
+
+        if slot.is_none() && is_variable_name(name) && taken.insert(name.clone())
+        {
+            *slot = Some(name.clone());
+        }
+    }
+
+    let mut names = Vec::<String>::with_capacity(cnf.num_vars);
+
+    for (index, name) in named.into_iter().enumerate()
+    {
+        let name = name.unwrap_or_else(|| {
+            (0..).map(|suffix| format!("x{}{}", index + 1, "a".repeat(suffix))).find(|name| !taken.contains(name)).unwrap()
+        });
+
+        taken.insert(name.clone());
+        names.push(name);
+    }
+
+    return Ok((cnf, names));
+}
+
+/**
+ * Renders `cnf` as formula source, each clause as an OR of its literals. Clauses are joined by AND
+ * in a balanced tree so large instances do not turn into a deeply nested expression. A variable
+ * that appears in no clause is kept with the clause `(x | !x)`, so that it still doubles the
+ * number of models as it does for the CNF
+**/
+pub fn cnf_to_source(cnf: &Cnf, names: &[String]) -> String
+{
+    fn join(clauses: &[String]) -> String
+    {
+        if clauses.len() == 1
+        {
+            return clauses[0].clone();
+        }
+
+        let (left, right) = clauses.split_at(clauses.len() / 2);
+        return [SYMBOL_LEFT_PAREN, &join(left), SYMBOL_AND, &join(right), SYMBOL_RIGHT_PAREN].join("");
+    }
+
+    let mut used = vec![false; cnf.num_vars];
+    for lit in cnf.clauses.iter().flatten()
+    {
+        used[(lit.unsigned_abs() - 1) as usize] = true;
+    }
+
+    let unused = (0..cnf.num_vars).filter(|var| !used[*var]).map(|var| vec![(var, true), (var, false)]);
+
+    let clauses = cnf.clauses.iter()
+        .map(|clause| clause.iter().map(|lit| ((lit.unsigned_abs() - 1) as usize, *lit > 0)).collect::<Term>())
+        .chain(unused)
+        .map(|term| canonical::render_product_of_sums(names, &[term]))
+        .collect::<Vec<String>>();
+
+    if clauses.is_empty()
+    {
+        return canonical::render_product_of_sums(names, &[]);
+    }
+
+    return join(&clauses);
+}
@@ -17,7 +17,7 @@ pub enum NodeOperation
     IndexedSubexpression(u32)
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ASTNode
 {
     pub op: NodeOperation,
@@ -32,6 +32,48 @@ impl ASTNode
     {
         ASTNode { op, left: None, right: None }
     }
+
+    pub fn variable(location: VarLocation) -> Box<ASTNode>
+    {
+        Box::new(Self::create(NodeOperation::VariableDeref(location)))
+    }
+
+    pub fn literal(val: bool) -> Box<ASTNode>
+    {
+        Box::new(Self::create(NodeOperation::Literal(val)))
+    }
+
+    pub fn unary(op: OperatorType, operand: Box<ASTNode>) -> Box<ASTNode>
+    {
+        let mut node = Self::create(NodeOperation::UnaryOperation(op));
+        node.left = Some(operand);
+        Box::new(node)
+    }
+
+    pub fn binary(op: OperatorType, left: Box<ASTNode>, right: Box<ASTNode>) -> Box<ASTNode>
+    {
+        let mut node = Self::create(NodeOperation::BinaryOperation(op));
+        node.left = Some(left);
+        node.right = Some(right);
+        Box::new(node)
+    }
+}
+
+impl Drop for ASTNode
+{
+    // Frees the children without recursion, so that trees too deep to process can still be dropped
+    fn drop(&mut self)
+    {
+        let mut pending = Vec::<Box<ASTNode>>::new();
+        pending.extend(self.left.take());
+        pending.extend(self.right.take());
+
+        while let Some(mut node) = pending.pop()
+        {
+            pending.extend(node.left.take());
+            pending.extend(node.right.take());
+        }
+    }
 }
 
 fn stash_prev_op(current_op: OperatorType, target_op: OperatorType) -> bool
@@ -64,20 +106,20 @@ fn create_operation_node(node_stack: &mut NodeStack, op: OperatorType) -> Result
             node.right = node_stack.pop();
             node.left = node_stack.pop();
 
-            if let None = node.left     { return Err(()); };
-            if let None = node.right    { return Err(()); };
+            if node.left.is_none()      { return Err(()); };
+            if node.right.is_none()     { return Err(()); };
         },
         OperatorType::NOT => {
             node = ASTNode::create(NodeOperation::UnaryOperation(op));
             node.left = node_stack.pop();
-            if let None = node.left     { return Err(()); };
+            if node.left.is_none()      { return Err(()); };
         }
     };
 
     return Ok(node);
 }
 
-fn peek_stack<'a, T>(stack: &'a Vec<T>) -> &'a T
+fn peek_stack<T>(stack: &[T]) -> &T
 {
     return &stack[stack.len() - 1];
 }
@@ -90,11 +132,21 @@ pub struct CompiledSyntaxBTree<'a>
 }
 
 pub fn compile<'a>(tokens: &'a Vec<Token>) -> CompiledSyntaxBTree<'a>
+{
+    return compile_with_variables(tokens, vec![]);
+}
+
+/**
+ * Same as `compile`, but starts from an existing list of variables. Variables already in the
+ * list keep their locations and new ones are appended, so several formulas compiled this way
+ * can be evaluated against the same values
+**/
+pub fn compile_with_variables<'a>(tokens: &'a Vec<Token>, variables: Vec<String>) -> CompiledSyntaxBTree<'a>
 {
     let mut node_stack: NodeStack = vec![];
     let mut operands_stack: Vec<TokenType> = vec![];
 
-    let mut variables: Vec<String> = vec![];
+    let mut variables = variables;
 
     let mut error = false;
     let mut error_token_ref: &Token = &tokens[0];
@@ -107,7 +159,7 @@ pub fn compile<'a>(tokens: &'a Vec<Token>) -> CompiledSyntaxBTree<'a>
 
         match token.token_type
         {
-            TokenType::Error => { error = true; error_token_ref = &token; break; },
+            TokenType::Error => { error = true; error_token_ref = token; break; },
 
             TokenType::LeftParen => { operands_stack.push(TokenType::LeftParen); },
             TokenType::LeftBrace => { operands_stack.push(TokenType::LeftBrace); },
@@ -144,7 +196,7 @@ pub fn compile<'a>(tokens: &'a Vec<Token>) -> CompiledSyntaxBTree<'a>
                         match node
                         {
                             Ok(n) => { node_stack.push(Box::new(n)); }
-                            _ => { error = true; error_token_ref = &token; break; }
+                            _ => { error = true; error_token_ref = token; break; }
                         }
                         
                         operands_stack.pop();
@@ -171,7 +223,7 @@ pub fn compile<'a>(tokens: &'a Vec<Token>) -> CompiledSyntaxBTree<'a>
                         match node
                         {
                             Ok(n) => { node_stack.push(Box::new(n)); }
-                            _ => { error = true; error_token_ref = &token; break; }
+                            _ => { error = true; error_token_ref = token; break; }
                         }
                         
                         operands_stack.pop();
@@ -185,7 +237,7 @@ pub fn compile<'a>(tokens: &'a Vec<Token>) -> CompiledSyntaxBTree<'a>
                         operands_stack.pop();
 
                         // Prevent redundant nested groups
-                        if node_stack.len() > 0 && node_stack[node_stack.len() - 1].op != NodeOperation::Subexpression 
+                        if !node_stack.is_empty() && node_stack[node_stack.len() - 1].op != NodeOperation::Subexpression 
                         {
                             let mut node = ASTNode::create(NodeOperation::Subexpression);
                             node.left = node_stack.pop();
@@ -195,7 +247,7 @@ pub fn compile<'a>(tokens: &'a Vec<Token>) -> CompiledSyntaxBTree<'a>
                     }
                     else {
                         error = true;
-                        error_token_ref = &token;
+                        error_token_ref = token;
                         break;
                     }
                 }
@@ -205,14 +257,14 @@ pub fn compile<'a>(tokens: &'a Vec<Token>) -> CompiledSyntaxBTree<'a>
     };
 
     let mut result = CompiledSyntaxBTree {
-        error_token: if error { Some(error_token_ref.clone()) } else { None },
+        error_token: if error { Some(*error_token_ref) } else { None },
         root: None,
         variables
     };
 
     if !error
     {
-        if operands_stack.len() == 0 && node_stack.len() == 1
+        if operands_stack.is_empty() && node_stack.len() == 1
         {
             result.root = node_stack.pop();
         }
@@ -0,0 +1,428 @@
+use crate::canonical::Term;
+use crate::compiler::ASTNode;
+use crate::minimize::Implicant;
+use crate::transform::{self, TransformError};
+use std::collections::{HashMap, HashSet};
+
+/// Largest number of functions minimized together, outputs of a cube are bits of a `u64`
+pub const MAX_OUTPUTS: usize = 64;
+
+/// Reduce, expand and irredundant are repeated at most this many times after the first pass
+const MAX_ITERATIONS: usize = 16;
+
+/// A product term shared by several functions: it is part of the sum of output `j` when bit `j` of `outputs` is set
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cube
+{
+    pub inputs: Implicant,
+    pub outputs: u64,
+}
+
+/**
+ * Where each output must be false: everywhere outside a list of cubes per output, or the rows
+ * set in one vector per output indexed by minterm (see `table::truth_vector`)
+**/
+pub enum OffSet
+{
+    Complement(Vec<Vec<Implicant>>),
+    Table(Vec<Vec<bool>>),
+}
+
+impl OffSet
+{
+    fn intersects(&self, cube: &Implicant, output: usize, size: u32) -> bool
+    {
+        match self
+        {
+            OffSet::Complement(on) => !covers(&on[output], cube, size),
+            OffSet::Table(rows) => {
+                // Visit every minterm of the cube by counting down through the subsets of its free bits
+                let mut free = cube.mask;
+                loop {
+                    if rows[output][(cube.value | free) as usize] { return true; }
+                    if free == 0 { return false; }
+                    free = (free - 1) & cube.mask;
+                }
+            },
+        }
+    }
+}
+
+/// Merges cubes with the same inputs into one cube for all of their outputs
+fn merge_outputs(cubes: impl Iterator<Item = Cube>) -> Vec<Cube>
+{
+    let mut merged = HashMap::<Implicant, u64>::new();
+    for cube in cubes
+    {
+        *merged.entry(cube.inputs).or_insert(0) |= cube.outputs;
+    }
+
+    let mut cubes = merged.into_iter().map(|(inputs, outputs)| Cube { inputs, outputs }).collect::<Vec<Cube>>();
+    cubes.sort_by_key(|cube| (cube.inputs, cube.outputs));
+    return cubes;
+}
+
+/// The functions to minimize together, one on-set, off-set and don't-care set per output
+pub struct Specification
+{
+    pub on: Vec<Cube>,
+    pub off: OffSet,
+    /// Cubes each output may cover or not, one list per output
+    pub dont_care: Vec<Vec<Implicant>>,
+}
+
+/**
+ * Functions given by truth vectors (see `table::truth_vector`), one minterm cube per true row.
+ * `dont_cares` holds a vector of the same form per output, or nothing, its rows are neither in
+ * the on-set nor in the off-set
+**/
+pub fn from_truth_vectors(truths: &[Vec<bool>], dont_cares: &[Vec<bool>]) -> Specification
+{
+    let free = |output: usize, minterm: u32| dont_cares.get(output).and_then(|dont_care| dont_care.get(minterm as usize)) == Some(&true);
+
+    let on = truths.iter().enumerate()
+        .flat_map(|(output, truth)| {
+            (0..truth.len() as u32)
+                .filter(move |minterm| truth[*minterm as usize] && !free(output, *minterm))
+                .map(move |minterm| Cube { inputs: Implicant::minterm(minterm), outputs: 1 << output })
+        });
+
+    let off = truths.iter().enumerate()
+        .map(|(output, truth)| (0..truth.len() as u32).map(|minterm| !truth[minterm as usize] && !free(output, minterm)).collect())
+        .collect();
+
+    // Merged into larger cubes, they are only ever used to check coverage
+    let dont_care = (0..truths.len())
+        .map(|output| match dont_cares.get(output)
+        {
+            Some(rows) if !rows.is_empty() => block_cubes(rows, 0, rows.len() as u32),
+            _ => vec![],
+        })
+        .collect();
+
+    return Specification { on: merge_outputs(on), off: OffSet::Table(off), dont_care };
+}
+
+/**
+ * Cubes covering exactly the rows set in `rows` (indexed by minterm) within the block of `len`
+ * rows from `start`, an aligned power of two. Splits the block on its first variable and merges
+ * the cubes both halves have in common, so it takes time linear in the rows where listing every
+ * prime implicant can take 3^n
+**/
+fn block_cubes(rows: &[bool], start: u32, len: u32) -> Vec<Implicant>
+{
+    let block = &rows[start as usize..(start + len) as usize];
+
+    if block.iter().all(|row| *row) { return vec![Implicant { value: start, mask: len - 1 }]; }
+    if !block.iter().any(|row| *row) { return vec![]; }
+
+    let half = len / 2;
+    let mut high = block_cubes(rows, start + half, half).into_iter().collect::<HashSet<Implicant>>();
+    let mut cubes = Vec::<Implicant>::new();
+
+    for cube in block_cubes(rows, start, half)
+    {
+        match high.remove(&Implicant { value: cube.value | half, mask: cube.mask })
+        {
+            true => cubes.push(Implicant { value: cube.value, mask: cube.mask | half }),
+            false => cubes.push(cube),
+        }
+    }
+
+    cubes.extend(high);
+    cubes.sort_unstable();
+    return cubes;
+}
+
+/**
+ * Functions rooted at `nodes`, with on-set cubes from the DNF of every function and the off-set
+ * everything they and the don't-care cubes of the output (one list per output, or nothing) leave
+ * out. Needs no table, so it works past the size of a table as long as the DNFs stay within
+ * `limit` products
+**/
+pub fn from_nodes(size: u32, nodes: &[Box<ASTNode>], dont_cares: Vec<Vec<Implicant>>, limit: usize) -> Result<Specification, TransformError>
+{
+    let mut dont_care = dont_cares;
+    dont_care.resize(nodes.len(), vec![]);
+
+    let mut on = Vec::<Cube>::new();
+    let mut allowed = Vec::<Vec<Implicant>>::with_capacity(nodes.len());
+
+    for (output, node) in nodes.iter().enumerate()
+    {
+        let cubes = transform::to_dnf(node, limit)?.iter().map(|term| Implicant::from_term(size, term)).collect::<Vec<Implicant>>();
+        on.extend(cubes.iter().map(|cube| Cube { inputs: *cube, outputs: 1 << output }));
+        allowed.push(cubes.into_iter().chain(dont_care[output].iter().copied()).collect());
+    }
+
+    return Ok(Specification { on: merge_outputs(on.into_iter()), off: OffSet::Complement(allowed), dont_care });
+}
+
+/**
+ * Whether the cubes cover every minterm of the variables in `free`, splitting on one variable at
+ * a time. Variables that appear with a single polarity are dropped instead of split on: the
+ * cubes mentioning them can only cover what the others already have to
+**/
+fn tautology(cubes: &[Implicant], free: u32) -> bool
+{
+    if cubes.iter().any(|cube| cube.mask & free == free) { return true; }
+
+    // Too few minterms between them to cover everything
+    let volume = cubes.iter().map(|cube| 1u64 << (cube.mask & free).count_ones()).sum::<u64>();
+    if volume < 1u64 << free.count_ones() { return false; }
+
+    let mut best: Option<(u32, usize)> = None;
+    for bit in (0..32).map(|col| 1u32 << col).filter(|bit| free & bit != 0)
+    {
+        let positive = cubes.iter().filter(|cube| cube.mask & bit == 0 && cube.value & bit != 0).count();
+        let negative = cubes.iter().filter(|cube| cube.mask & bit == 0 && cube.value & bit == 0).count();
+
+        if positive + negative == 0 { continue; }
+
+        if positive == 0 || negative == 0
+        {
+            let independent = cubes.iter().filter(|cube| cube.mask & bit != 0).copied().collect::<Vec<Implicant>>();
+            return tautology(&independent, free & !bit);
+        }
+
+        if best.is_none_or(|(_, count)| positive + negative > count)
+        {
+            best = Some((bit, positive + negative));
+        }
+    }
+
+    let Some((bit, _)) = best else { return false; };
+
+    return [0, bit].iter().all(|half| {
+        let cofactor = cubes.iter()
+            .filter(|cube| cube.mask & bit != 0 || cube.value & bit == *half)
+            .map(|cube| Implicant { value: cube.value & !bit, mask: cube.mask | bit })
+            .collect::<Vec<Implicant>>();
+        tautology(&cofactor, free & !bit)
+    });
+}
+
+/// Whether the union of `cubes` contains `target`, by checking that their cofactor against it is a tautology
+fn covers(cubes: &[Implicant], target: &Implicant, size: u32) -> bool
+{
+    let all = ((1u64 << size) - 1) as u32;
+    let fixed = all & !target.mask;
+
+    let cofactor = cubes.iter()
+        .filter(|cube| cube.intersects(target))
+        .map(|cube| Implicant { value: cube.value & !fixed, mask: cube.mask | fixed })
+        .collect::<Vec<Implicant>>();
+
+    return tautology(&cofactor, target.mask);
+}
+
+/// Inputs of the cubes other than `skip` that are part of `output`, along with its don't-cares
+fn others(cover: &[Cube], skip: usize, output: usize, dont_care: &[Vec<Implicant>]) -> Vec<Implicant>
+{
+    return cover.iter().enumerate()
+        .filter(|(index, cube)| *index != skip && cube.outputs >> output & 1 == 1)
+        .map(|(_, cube)| cube.inputs)
+        .chain(dont_care[output].iter().copied())
+        .collect();
+}
+
+fn outputs_of(cube: &Cube, output_count: usize) -> impl Iterator<Item = usize>
+{
+    let outputs = cube.outputs;
+    return (0..output_count).filter(move |output| outputs >> output & 1 == 1);
+}
+
+/// The bits of the variables a cube fixes, or with `free` set the ones it leaves free
+fn input_bits(cube: &Cube, size: u32, free: bool) -> Vec<u32>
+{
+    return (0..size).map(|col| 1u32 << col).filter(|bit| (cube.inputs.mask & bit != 0) == free).collect();
+}
+
+/**
+ * Makes every cube as large as the off-set allows, first freeing input variables and then adding
+ * outputs, and drops the cubes that end up inside another one. Large cubes go first so the
+ * small ones are likely to be swallowed before their turn
+**/
+fn expand(cover: Vec<Cube>, size: u32, output_count: usize, off: &OffSet) -> Vec<Cube>
+{
+    let mut cover = cover;
+    cover.sort_by_key(|cube| std::cmp::Reverse(cube.inputs.mask.count_ones()));
+
+    let inputs = cover.iter().map(|cube| cube.inputs).collect::<Vec<Implicant>>();
+    let mut expanded = Vec::<Cube>::with_capacity(cover.len());
+
+    for cube in cover
+    {
+        let contained = |cube: &Cube, by: &Cube| by.inputs.contains(&cube.inputs) && by.outputs & cube.outputs == cube.outputs;
+        if expanded.iter().any(|by| contained(&cube, by)) { continue; }
+
+        let mut cube = cube;
+
+        // Raise first the variables that keep the most other cubes out, growing towards them. Cubes
+        // kept out by that variable alone come first, raising it takes them in
+        let conflicts = inputs.iter()
+            .map(|other| (other.value ^ cube.inputs.value) & !other.mask & !cube.inputs.mask)
+            .collect::<Vec<u32>>();
+
+        let mut bits = input_bits(&cube, size, false);
+        bits.sort_by_key(|bit| {
+            let only = conflicts.iter().filter(|conflict| *conflict == bit).count();
+            let any = conflicts.iter().filter(|conflict| *conflict & bit != 0).count();
+            std::cmp::Reverse((only, any))
+        });
+
+        for bit in bits
+        {
+            let raised = Implicant { value: cube.inputs.value & !bit, mask: cube.inputs.mask | bit };
+            if outputs_of(&cube, output_count).all(|output| !off.intersects(&raised, output, size))
+            {
+                cube.inputs = raised;
+            }
+        }
+
+        for output in 0..output_count
+        {
+            if !off.intersects(&cube.inputs, output, size) { cube.outputs |= 1 << output; }
+        }
+
+        expanded.retain(|other| !contained(other, &cube));
+        expanded.push(cube);
+    }
+
+    return expanded;
+}
+
+/**
+ * Removes outputs from cubes, and cubes without outputs, while the rest still covers them. Small
+ * cubes are tried first as they are the most likely to be redundant
+**/
+fn irredundant(cover: Vec<Cube>, size: u32, output_count: usize, dont_care: &[Vec<Implicant>]) -> Vec<Cube>
+{
+    let mut cover = cover;
+    cover.sort_by_key(|cube| cube.inputs.mask.count_ones());
+
+    for index in 0..cover.len()
+    {
+        for output in outputs_of(&cover[index], output_count)
+        {
+            if covers(&others(&cover, index, output, dont_care), &cover[index].inputs, size)
+            {
+                cover[index].outputs &= !(1 << output);
+            }
+        }
+    }
+
+    cover.retain(|cube| cube.outputs != 0);
+    return cover;
+}
+
+/// The smallest part of `cover[index]` the other cubes do not already cover, one input variable at a time
+fn reduce_cube(cover: &[Cube], index: usize, size: u32, output_count: usize, dont_care: &[Vec<Implicant>]) -> Implicant
+{
+    let mut cube = cover[index];
+
+    for bit in input_bits(&cube, size, true)
+    {
+        let half = |value: u32| Implicant { value: cube.inputs.value | value, mask: cube.inputs.mask & !bit };
+
+        for (dropped, kept) in [(half(bit), half(0)), (half(0), half(bit))]
+        {
+            if outputs_of(&cube, output_count).all(|output| covers(&others(cover, index, output, dont_care), &dropped, size))
+            {
+                cube.inputs = kept;
+                break;
+            }
+        }
+    }
+
+    return cube.inputs;
+}
+
+/**
+ * Shrinks every cube to the part the other cubes do not already cover, largest first and taking
+ * the cubes reduced so far into account. This gives the next expand a chance to grow the cubes
+ * in a different direction
+**/
+fn reduce(cover: Vec<Cube>, size: u32, output_count: usize, dont_care: &[Vec<Implicant>]) -> Vec<Cube>
+{
+    let mut cover = cover;
+    cover.sort_by_key(|cube| std::cmp::Reverse(cube.inputs.mask.count_ones()));
+
+    for index in 0..cover.len()
+    {
+        cover[index].inputs = reduce_cube(&cover, index, size, output_count, dont_care);
+    }
+
+    return cover;
+}
+
+/**
+ * A final attempt once reduce and expand stop helping: every cube is reduced against all the
+ * others at once and expanded on its own, and the new cubes this finds compete with the old ones
+ * in irredundant
+**/
+fn last_gasp(cover: Vec<Cube>, size: u32, output_count: usize, spec: &Specification) -> Vec<Cube>
+{
+    let reduced = (0..cover.len())
+        .map(|index| Cube { inputs: reduce_cube(&cover, index, size, output_count, &spec.dont_care), outputs: cover[index].outputs })
+        .collect::<Vec<Cube>>();
+
+    let mut candidates = expand(reduced, size, output_count, &spec.off);
+    candidates.retain(|cube| !cover.contains(cube));
+
+    // Irredundant tries to drop the first of equally large cubes first, old cubes go before new ones
+    let mut cover = cover;
+    cover.extend(candidates);
+    return irredundant(cover, size, output_count, &spec.dont_care);
+}
+
+/// Number of cubes, then number of input literals
+fn cost(cover: &[Cube], size: u32) -> (usize, u32)
+{
+    return (cover.len(), cover.iter().map(|cube| cube.inputs.literal_count(size)).sum());
+}
+
+/**
+ * Heuristic two-level minimization of `output_count` functions of `size` variables in the manner
+ * of Espresso: the on-set cubes are expanded against the off-set and made irredundant, then
+ * reduce, expand and irredundant are repeated while the cover keeps getting cheaper, with one
+ * last gasp before giving up. The result covers the on-set and nothing of the off-set of every
+ * output but is not guaranteed to be minimal
+**/
+pub fn minimize(size: u32, output_count: usize, spec: Specification) -> Vec<Cube>
+{
+    let mut spec = spec;
+    spec.dont_care.resize(output_count, vec![]);
+
+    let on = std::mem::take(&mut spec.on);
+    let (off, dont_care) = (&spec.off, &spec.dont_care);
+
+    let mut cover = irredundant(expand(on, size, output_count, off), size, output_count, dont_care);
+    let mut best = cover.clone();
+
+    for _ in 0..MAX_ITERATIONS
+    {
+        cover = reduce(cover, size, output_count, dont_care);
+        cover = irredundant(expand(cover, size, output_count, off), size, output_count, dont_care);
+
+        if cost(&cover, size) >= cost(&best, size)
+        {
+            cover = last_gasp(best.clone(), size, output_count, &spec);
+            if cost(&cover, size) >= cost(&best, size) { break; }
+        }
+
+        best = cover.clone();
+    }
+
+    best.sort_by_key(|cube| (cube.inputs.literal_count(size), cube.inputs.to_term(size)));
+    return best;
+}
+
+/// The products of `cover` that are part of `output`, in the form of `canonical::render_sum_of_products`
+pub fn output_products(cover: &[Cube], size: u32, output: usize) -> Vec<Term>
+{
+    return cover.iter()
+        .filter(|cube| cube.outputs >> output & 1 == 1)
+        .map(|cube| cube.inputs.to_term(size))
+        .collect();
+}
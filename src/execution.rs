@@ -1,24 +1,41 @@
 use crate::compiler::{NodeOperation, ASTNode};
 use crate::scanner::OperatorType;
 
-pub fn postorder_traversal_postfix(node: &Box<ASTNode>, output: &mut Vec<NodeOperation>, start_index: usize) -> usize
-{   
+/**
+ * Deepest expression tree `subexpression_groups` accepts. Every pass over a tree recurses once
+ * per level, deeper trees are rejected before they can overflow the stack
+**/
+pub const MAX_DEPTH: usize = 2000;
+
+fn postorder_traversal_impl(node: &ASTNode, output: &mut Vec<NodeOperation>, depth: usize) -> Result<usize, EvalError>
+{
+    if depth > MAX_DEPTH
+    {
+        return Err(EvalError::TooDeep { max: MAX_DEPTH });
+    }
+
     let mut w_left: usize   = 0;
     let mut w_right: usize  = 0;
 
     if let Some(child) = &node.left
     {
-        w_left = postorder_traversal_postfix(child, output, start_index);
+        w_left = postorder_traversal_impl(child, output, depth + 1)?;
 
         if let Some(child) = &node.right
         {
-            w_right = postorder_traversal_postfix(child, output, start_index + w_left);
+            w_right = postorder_traversal_impl(child, output, depth + 1)?;
         }
     }
 
     output.push(node.op);
 
-    return w_left + w_right + 1;
+    return Ok(w_left + w_right + 1);
+}
+
+/// Appends the nodes of the tree to `output` in postfix order, fails for trees deeper than `MAX_DEPTH`
+pub fn postorder_traversal_postfix(node: &ASTNode, output: &mut Vec<NodeOperation>) -> Result<usize, EvalError>
+{
+    return postorder_traversal_impl(node, output, 1);
 }
 
 /**
@@ -51,15 +68,15 @@ fn op_backtrack_size(op: &NodeOperation) -> u8
  *    [ var(p), var(q), op(or), | var(p), var(q), op(and), op(not), | SUB, op(and) ]
  *                              |-----------------------------------|  
  **/
-fn subexpression_backtrack_size(pf_list: &Vec<NodeOperation>, sub_loc: usize) -> usize
+fn subexpression_backtrack_size(pf_list: &[NodeOperation], sub_loc: usize) -> usize
 {
-    let mut remaining = 1;
+    let mut remaining: usize = 1;
     let mut consumed = 0;
 
     while remaining != 0
     {
         consumed += 1;
-        remaining = remaining - 1 + op_backtrack_size(&pf_list[sub_loc - consumed]);
+        remaining = remaining - 1 + op_backtrack_size(&pf_list[sub_loc - consumed]) as usize;
     }
 
     return consumed;
@@ -72,7 +89,7 @@ fn subexpression_groups_impl(pf_list: Vec<NodeOperation>, locations: Vec<usize>)
 
     let mut total_removed = 0;
 
-    for (index, loc) in locations.into_iter().enumerate()
+    for loc in locations
     {
         let loc = loc - total_removed;
         let mut grp = Vec::<NodeOperation>::with_capacity(loc);
@@ -88,19 +105,19 @@ fn subexpression_groups_impl(pf_list: Vec<NodeOperation>, locations: Vec<usize>)
     return groups;
 }
 
-pub fn subexpression_groups(node: &Box<ASTNode>) -> Vec<Vec<NodeOperation>>
+pub fn subexpression_groups(node: &ASTNode) -> Result<Vec<Vec<NodeOperation>>, EvalError>
 {
     // 1 . First convert the expression tree to its postfix representation
 
     let mut as_list = Vec::<NodeOperation>::new();
-    let count = postorder_traversal_postfix(node, &mut as_list, 0);
+    let count = postorder_traversal_postfix(node, &mut as_list)?;
 
     let mut locations: Vec<usize> = vec![];
-    for i in 0..count
+    for (i, op) in as_list.iter_mut().enumerate()
     {
-        if as_list[i] == NodeOperation::Subexpression
+        if *op == NodeOperation::Subexpression
         {
-            as_list[i] = NodeOperation::IndexedSubexpression(locations.len() as u32);
+            *op = NodeOperation::IndexedSubexpression(locations.len() as u32);
             locations.push(i);
         }
     }
@@ -120,14 +137,113 @@ pub fn subexpression_groups(node: &Box<ASTNode>) -> Vec<Vec<NodeOperation>>
 
     // 2 . Then divide the list into subexpresion groups
 
-    return subexpression_groups_impl(as_list, locations);
+    return Ok(subexpression_groups_impl(as_list, locations));
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EvalError
+{
+    /// An operation in `group` did not have enough operands on the stack
+    MissingOperand { group: usize },
+    /// `group` left no value or more than one value on the stack
+    UnbalancedGroup { group: usize },
+    /// `group` references a subexpression group that is not evaluated before it
+    UnresolvedSubexpression { group: usize, index: u32 },
+    /// `group` dereferences a variable that has no value
+    UnknownVariable { group: usize, location: usize },
+    /// `group` applies an operator with the wrong number of operands (e.g. a unary AND)
+    UnsupportedOperation { group: usize, op: NodeOperation },
+    /// The output buffer cannot hold a value for every group
+    OutputTooSmall { required: usize, available: usize },
+    /// A full table was requested for more variables than rows can be counted for
+    TooManyVariables { count: usize, max: usize },
+    /// The expression tree is nested deeper than `max` levels
+    TooDeep { max: usize },
+}
+
+impl std::fmt::Display for EvalError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self
+        {
+            EvalError::MissingOperand { group } => write!(f, "Operand not found in group {}", group),
+            EvalError::UnbalancedGroup { group } => write!(f, "Group {} does not evaluate to a single value", group),
+            EvalError::UnresolvedSubexpression { group, index } =>
+                write!(f, "Group {} references subexpression {} before it is evaluated", group, index),
+            EvalError::UnknownVariable { group, location } =>
+                write!(f, "Group {} references unknown variable #{}", group, location),
+            EvalError::UnsupportedOperation { group, op } =>
+                write!(f, "Unhandled operation {:?} in group {}", op, group),
+            EvalError::OutputTooSmall { required, available } =>
+                write!(f, "Output holds {} values but {} groups were given", available, required),
+            EvalError::TooManyVariables { count, max } =>
+                write!(f, "Cannot tabulate {} variables, at most {} are supported", count, max),
+            EvalError::TooDeep { max } => write!(f, "Formula is nested more than {} levels deep", max),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+/**
+ * Checks that `groups` can be evaluated with `var_count` variables: every operation has its
+ * operands, every group produces exactly one value and only references earlier groups.
+ * When this passes, `evaluate` and `groups_to_string` cannot fail on the same groups
+**/
+pub fn validate_groups(groups: &[Vec<NodeOperation>], var_count: usize) -> Result<(), EvalError>
+{
+    for (index, grp) in groups.iter().enumerate()
+    {
+        let mut depth: usize = 0;
+
+        for op in grp
+        {
+            match op
+            {
+                NodeOperation::VariableDeref(loc) if *loc >= var_count => {
+                    return Err(EvalError::UnknownVariable { group: index, location: *loc });
+                },
+                NodeOperation::IndexedSubexpression(sub_loc) if *sub_loc as usize >= index => {
+                    return Err(EvalError::UnresolvedSubexpression { group: index, index: *sub_loc });
+                },
+                NodeOperation::BinaryOperation(OperatorType::NOT)
+                | NodeOperation::UnaryOperation(OperatorType::AND)
+                | NodeOperation::UnaryOperation(OperatorType::OR)
+                | NodeOperation::UnaryOperation(OperatorType::CNDL)
+                | NodeOperation::UnaryOperation(OperatorType::BI_CNDL) => {
+                    return Err(EvalError::UnsupportedOperation { group: index, op: *op });
+                },
+                NodeOperation::Subexpression => { continue; },
+                _ => ()
+            }
+
+            let required = op_backtrack_size(op) as usize;
+            if depth < required
+            {
+                return Err(EvalError::MissingOperand { group: index });
+            }
+            depth = depth - required + 1;
+        }
+
+        if depth != 1
+        {
+            return Err(EvalError::UnbalancedGroup { group: index });
+        }
+    }
+
+    return Ok(());
 }
 
-pub fn evaluate(groups: &Vec<Vec<NodeOperation>>, values: &[bool], out_eval: &mut [bool])
+pub fn evaluate(groups: &[Vec<NodeOperation>], values: &[bool], out_eval: &mut [bool]) -> Result<(), EvalError>
 {
+    if out_eval.len() < groups.len()
+    {
+        return Err(EvalError::OutputTooSmall { required: groups.len(), available: out_eval.len() });
+    }
+
     let mut operands_stack = Vec::<bool>::with_capacity(100);
-    let mut index = 0;
-    for grp in groups
+    for (index, grp) in groups.iter().enumerate()
     {
         operands_stack.clear();
 
@@ -136,13 +252,20 @@ pub fn evaluate(groups: &Vec<Vec<NodeOperation>>, values: &[bool], out_eval: &mu
             match op
             {
                 NodeOperation::Literal(val) => { operands_stack.push(*val); },
-                NodeOperation::VariableDeref(loc) => { operands_stack.push(values[*loc]); },
+                NodeOperation::VariableDeref(loc) => {
+                    let val = values.get(*loc).ok_or(EvalError::UnknownVariable { group: index, location: *loc })?;
+                    operands_stack.push(*val);
+                },
                 NodeOperation::IndexedSubexpression(sub_loc) => {
+                    if *sub_loc as usize >= index
+                    {
+                        return Err(EvalError::UnresolvedSubexpression { group: index, index: *sub_loc });
+                    }
                     operands_stack.push(out_eval[*sub_loc as usize]);
                 },
                 NodeOperation::BinaryOperation(op_type) => {
-                    let right = operands_stack.pop().expect("Operand not found");
-                    let left = operands_stack.pop().expect("Operand not found");
+                    let right = operands_stack.pop().ok_or(EvalError::MissingOperand { group: index })?;
+                    let left = operands_stack.pop().ok_or(EvalError::MissingOperand { group: index })?;
 
                     let result = match *op_type
                     {
@@ -150,18 +273,18 @@ pub fn evaluate(groups: &Vec<Vec<NodeOperation>>, values: &[bool], out_eval: &mu
                         OperatorType::OR => left || right,
                         OperatorType::CNDL => !left || right,
                         OperatorType::BI_CNDL => left == right,
-                        _ => { panic!("Unhandled binary operation"); }
+                        _ => { return Err(EvalError::UnsupportedOperation { group: index, op: *op }); }
                     };
 
                     operands_stack.push(result)
                 },
                 NodeOperation::UnaryOperation(op_type) => {
-                    let left = operands_stack.pop().expect("Operand not found");
+                    let left = operands_stack.pop().ok_or(EvalError::MissingOperand { group: index })?;
 
                     let result = match *op_type
                     {
                         OperatorType::NOT => !left,
-                        _ => { panic!("Unhandled unary operation"); }
+                        _ => { return Err(EvalError::UnsupportedOperation { group: index, op: *op }); }
                     };
 
                     operands_stack.push(result)
@@ -170,29 +293,35 @@ pub fn evaluate(groups: &Vec<Vec<NodeOperation>>, values: &[bool], out_eval: &mu
             }
         }
 
-        let result = operands_stack.pop().expect("Broken expression");
+        let result = operands_stack.pop().ok_or(EvalError::UnbalancedGroup { group: index })?;
+        if !operands_stack.is_empty()
+        {
+            return Err(EvalError::UnbalancedGroup { group: index });
+        }
+
         out_eval[index] = result;
-        index += 1;
     }
+
+    return Ok(());
 }
 
-const SYMBOL_TRUE: &'static str = "<T>";
-const SYMBOL_FALSE: &'static str = "<F>";
-const SYMBOL_AND: &'static str = " & ";
-const SYMBOL_OR: &'static str = " | ";
-const SYMBOL_CNDL: &'static str = " => ";
-const SYMBOL_BI_CNDL: &'static str = " <=> ";
-const SYMBOL_NOT: &'static str = "!";
-const SYMBOL_LEFT_PAREN: &'static str = "(";
-const SYMBOL_RIGHT_PAREN: &'static str = ")";
+pub const SYMBOL_TRUE: &str = "<T>";
+pub const SYMBOL_FALSE: &str = "<F>";
+pub const SYMBOL_AND: &str = " & ";
+pub const SYMBOL_OR: &str = " | ";
+pub const SYMBOL_CNDL: &str = " => ";
+pub const SYMBOL_BI_CNDL: &str = " <=> ";
+pub const SYMBOL_NOT: &str = "!";
+pub const SYMBOL_LEFT_PAREN: &str = "(";
+pub const SYMBOL_RIGHT_PAREN: &str = ")";
 
 // TODO: Optimize. Horribly slow at the moment
-pub fn groups_to_string(groups: &Vec<Vec<NodeOperation>>, variables: &Vec<String>) -> Vec<String>
+pub fn groups_to_string(groups: &[Vec<NodeOperation>], variables: &[String]) -> Result<Vec<String>, EvalError>
 {
     let mut operands_stack = Vec::<String>::with_capacity(100);
     let mut result = Vec::<String>::with_capacity(groups.len());
 
-    for grp in groups
+    for (index, grp) in groups.iter().enumerate()
     {
         operands_stack.clear();
 
@@ -204,21 +333,24 @@ pub fn groups_to_string(groups: &Vec<Vec<NodeOperation>>, variables: &Vec<String
                     operands_stack.push(String::from( if *val { SYMBOL_TRUE } else { SYMBOL_FALSE } ));
                 },
                 NodeOperation::VariableDeref(loc) => { 
-                    operands_stack.push(variables[*loc].clone());
+                    let name = variables.get(*loc).ok_or(EvalError::UnknownVariable { group: index, location: *loc })?;
+                    operands_stack.push(name.clone());
                 },
                 NodeOperation::IndexedSubexpression(sub_loc) => {
-                    operands_stack.push(result[*sub_loc as usize].clone());
+                    let repr = result.get(*sub_loc as usize)
+                        .ok_or(EvalError::UnresolvedSubexpression { group: index, index: *sub_loc })?;
+                    operands_stack.push(repr.clone());
                 },
                 NodeOperation::BinaryOperation(op_type) => {
-                    let right = operands_stack.pop().unwrap();
-                    let left = operands_stack.pop().unwrap();
+                    let right = operands_stack.pop().ok_or(EvalError::MissingOperand { group: index })?;
+                    let left = operands_stack.pop().ok_or(EvalError::MissingOperand { group: index })?;
 
                     let symbol = match *op_type {
                         OperatorType::AND => SYMBOL_AND,
                         OperatorType::OR => SYMBOL_OR,
                         OperatorType::CNDL => SYMBOL_CNDL,
                         OperatorType::BI_CNDL => SYMBOL_BI_CNDL,
-                        _ => ""
+                        _ => { return Err(EvalError::UnsupportedOperation { group: index, op: *op }); }
                     };
 
                     operands_stack.push([
@@ -230,11 +362,11 @@ pub fn groups_to_string(groups: &Vec<Vec<NodeOperation>>, variables: &Vec<String
                     ].join(""));
                 },
                 NodeOperation::UnaryOperation(op_type) => {
-                    let left = operands_stack.pop().unwrap();
+                    let left = operands_stack.pop().ok_or(EvalError::MissingOperand { group: index })?;
 
                     let symbol = match *op_type {
                         OperatorType::NOT => SYMBOL_NOT,
-                        _ => ""
+                        _ => { return Err(EvalError::UnsupportedOperation { group: index, op: *op }); }
                     };
 
                     operands_stack.push([
@@ -248,8 +380,74 @@ pub fn groups_to_string(groups: &Vec<Vec<NodeOperation>>, variables: &Vec<String
             }
         }
 
-        result.push(operands_stack.pop().expect("Broken expression"));
+        let repr = operands_stack.pop().ok_or(EvalError::UnbalancedGroup { group: index })?;
+        if !operands_stack.is_empty()
+        {
+            return Err(EvalError::UnbalancedGroup { group: index });
+        }
+
+        result.push(repr);
+    }
+
+    return Ok(result);
+}
+
+/// Renders a whole expression tree the same way `groups_to_string` renders its last group
+pub fn node_to_string(node: &ASTNode, variables: &[String]) -> Result<String, EvalError>
+{
+    let groups = subexpression_groups(node)?;
+    let mut reprs = groups_to_string(&groups, variables)?;
+
+    return reprs.pop().ok_or(EvalError::UnbalancedGroup { group: 0 });
+}
+
+/**
+ * Rebuilds an expression tree for every subexpression group, the inverse of `subexpression_groups`.
+ * References to earlier groups are replaced by a copy of their tree, so each result stands alone
+**/
+pub fn groups_to_nodes(groups: &[Vec<NodeOperation>]) -> Result<Vec<Box<ASTNode>>, EvalError>
+{
+    let mut operands_stack = Vec::<Box<ASTNode>>::with_capacity(100);
+    let mut result = Vec::<Box<ASTNode>>::with_capacity(groups.len());
+
+    for (index, grp) in groups.iter().enumerate()
+    {
+        operands_stack.clear();
+
+        for op in grp
+        {
+            match op
+            {
+                NodeOperation::Literal(val) => operands_stack.push(ASTNode::literal(*val)),
+                NodeOperation::VariableDeref(loc) => operands_stack.push(ASTNode::variable(*loc)),
+                NodeOperation::IndexedSubexpression(sub_loc) => {
+                    let node = result.get(*sub_loc as usize)
+                        .ok_or(EvalError::UnresolvedSubexpression { group: index, index: *sub_loc })?;
+                    operands_stack.push(node.clone());
+                },
+                NodeOperation::BinaryOperation(op_type) => {
+                    let right = operands_stack.pop().ok_or(EvalError::MissingOperand { group: index })?;
+                    let left = operands_stack.pop().ok_or(EvalError::MissingOperand { group: index })?;
+                    operands_stack.push(ASTNode::binary(*op_type, left, right));
+                },
+                NodeOperation::UnaryOperation(op_type) => {
+                    let operand = operands_stack.pop().ok_or(EvalError::MissingOperand { group: index })?;
+                    operands_stack.push(ASTNode::unary(*op_type, operand));
+                },
+                NodeOperation::Subexpression => {
+                    return Err(EvalError::UnsupportedOperation { group: index, op: *op });
+                },
+            }
+        }
+
+        let node = operands_stack.pop().ok_or(EvalError::UnbalancedGroup { group: index })?;
+        if !operands_stack.is_empty()
+        {
+            return Err(EvalError::UnbalancedGroup { group: index });
+        }
+
+        result.push(node);
     }
 
-    return result;
+    return Ok(result);
 }
@@ -0,0 +1,320 @@
+use crate::compiler::{self, ASTNode, NodeOperation};
+use crate::execution::{self, EvalError};
+use crate::scanner::{self, TokenType};
+use crate::table;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FormulaError
+{
+    /// The source contained no tokens
+    Empty,
+    /// The source does not parse, the offending token's lexeme is attached
+    Syntax(String),
+    Eval(EvalError),
+    /// An assignment does not give a value to this variable of the formula
+    MissingVariable(String),
+    /// An assignment gives a value to a variable the formula does not contain
+    UnknownVariable(String),
+    /// An assignment entry that is not of the form `name=value`
+    InvalidAssignment(String),
+    /// A don't-care minterm list entry that is not a row index of the table
+    InvalidDontCare(String),
+}
+
+impl std::fmt::Display for FormulaError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self
+        {
+            FormulaError::Empty => write!(f, "Empty formula"),
+            FormulaError::Syntax(lexeme) => write!(f, "Error at token: \"{}\"", lexeme),
+            FormulaError::Eval(err) => write!(f, "Error: {}", err),
+            FormulaError::MissingVariable(name) => write!(f, "No value given for variable \"{}\"", name),
+            FormulaError::UnknownVariable(name) => write!(f, "Unknown variable \"{}\"", name),
+            FormulaError::InvalidAssignment(entry) =>
+                write!(f, "Invalid assignment \"{}\", expected name=T or name=F", entry),
+            FormulaError::InvalidDontCare(entry) => write!(f, "Invalid don't-care minterm \"{}\"", entry),
+        }
+    }
+}
+
+impl std::error::Error for FormulaError {}
+
+impl From<EvalError> for FormulaError
+{
+    fn from(err: EvalError) -> Self
+    {
+        FormulaError::Eval(err)
+    }
+}
+
+/**
+ * A parsed formula that owns everything derived from its source: the syntax tree, the
+ * variables in order of appearance, the subexpression groups and their printed form
+**/
+pub struct Formula
+{
+    pub root: Box<ASTNode>,
+    pub variables: Vec<String>,
+    pub groups: Vec<Vec<NodeOperation>>,
+    pub reprs: Vec<String>,
+}
+
+impl Formula
+{
+    pub fn parse(source: &str) -> Result<Formula, FormulaError>
+    {
+        return Self::parse_with_variables(source, vec![]);
+    }
+
+    /// Parses `source` on top of an existing variable list, see `compiler::compile_with_variables`
+    pub fn parse_with_variables(source: &str, variables: Vec<String>) -> Result<Formula, FormulaError>
+    {
+        let tokens = scanner::tokenize(source);
+
+        // empty query, only the EOF token is present
+        if tokens.len() == 1 { return Err(FormulaError::Empty); }
+
+        let compiled_result = compiler::compile_with_variables(&tokens, variables);
+
+        if let Some(error_token) = compiled_result.error_token
+        {
+            return Err(FormulaError::Syntax(String::from(error_token.lexeme)));
+        }
+
+        let root = compiled_result.root.ok_or(FormulaError::Syntax(String::from("<EOF>")))?;
+        let groups = execution::subexpression_groups(&root)?;
+        execution::validate_groups(&groups, compiled_result.variables.len())?;
+        let reprs = execution::groups_to_string(&groups, &compiled_result.variables)?;
+
+        return Ok(Formula {
+            root,
+            variables: compiled_result.variables,
+            groups,
+            reprs,
+        });
+    }
+
+    /**
+     * Evaluates every subexpression group for a single assignment given as (name, value)
+     * pairs. Every variable of the formula must be assigned and no others, when a variable is
+     * assigned more than once the last value wins
+    **/
+    pub fn evaluate_assignment(&self, assignment: &[(String, bool)]) -> Result<Vec<bool>, FormulaError>
+    {
+        let mut values: Vec<Option<bool>> = vec![None; self.variables.len()];
+
+        for (name, value) in assignment
+        {
+            let location = self.variables.iter().position(|var| var == name)
+                .ok_or(FormulaError::UnknownVariable(name.clone()))?;
+            values[location] = Some(*value);
+        }
+
+        let values = values.iter().zip(self.variables.iter())
+            .map(|(value, name)| value.ok_or(FormulaError::MissingVariable(name.clone())))
+            .collect::<Result<Vec<bool>, FormulaError>>()?;
+
+        let mut out_eval = vec![false; self.groups.len()];
+        execution::evaluate(&self.groups, &values, &mut out_eval)?;
+
+        return Ok(out_eval);
+    }
+
+    /**
+     * Parses the formula of a `dc:` clause over the variables of this formula, it may not
+     * introduce variables of its own
+    **/
+    pub fn parse_condition(&self, source: &str) -> Result<Formula, FormulaError>
+    {
+        let condition = Formula::parse_with_variables(source, self.variables.clone())?;
+
+        if let Some(name) = condition.variables.get(self.variables.len())
+        {
+            return Err(FormulaError::UnknownVariable(name.clone()));
+        }
+
+        return Ok(condition);
+    }
+
+    /// The rows of the table marked as don't-care, indexed by minterm (see `table::truth_vector`)
+    pub fn dont_care_vector(&self, dont_cares: &DontCares, threads: usize) -> Result<Vec<bool>, FormulaError>
+    {
+        let size = self.variables.len() as u32;
+
+        if size > table::MAX_VARIABLES
+        {
+            return Err(EvalError::TooManyVariables { count: size as usize, max: table::MAX_VARIABLES as usize }.into());
+        }
+
+        match dont_cares
+        {
+            DontCares::Condition(source) => {
+                let condition = self.parse_condition(source)?;
+                return Ok(table::truth_vector(&condition.groups, size, threads)?);
+            },
+            DontCares::Minterms(minterms) => {
+                let mut vector = vec![false; 1 << size];
+                for minterm in minterms
+                {
+                    *vector.get_mut(*minterm as usize).ok_or(FormulaError::InvalidDontCare(minterm.to_string()))? = true;
+                }
+
+                return Ok(vector);
+            },
+        }
+    }
+
+    /**
+     * The formula with the variables set in `dropped` (indexed by location) removed and fixed to
+     * false. Only meant for variables no column depends on (see `analysis::dependencies`), whose
+     * value does not matter, so the printed form of the columns is kept as it is
+    **/
+    pub fn without_variables(&self, dropped: &[bool]) -> Formula
+    {
+        let groups = self.groups.iter()
+            .map(|grp| grp.iter().map(|op| remap_variable(*op, dropped)).collect())
+            .collect();
+
+        let variables = self.variables.iter()
+            .zip(dropped.iter())
+            .filter(|(_, dropped)| !**dropped)
+            .map(|(var, _)| var.clone())
+            .collect();
+
+        return Formula {
+            root: remap_node(&self.root, dropped),
+            variables,
+            groups,
+            reprs: self.reprs.clone(),
+        };
+    }
+}
+
+/// Moves every variable reference to its location in a list with the variables in `dropped` removed
+fn remap_variable(op: NodeOperation, dropped: &[bool]) -> NodeOperation
+{
+    return match op
+    {
+        NodeOperation::VariableDeref(loc) if dropped[loc] => NodeOperation::Literal(false),
+        NodeOperation::VariableDeref(loc) => NodeOperation::VariableDeref(loc - dropped[..loc].iter().filter(|val| **val).count()),
+        _ => op,
+    };
+}
+
+fn remap_node(node: &ASTNode, dropped: &[bool]) -> Box<ASTNode>
+{
+    return Box::new(ASTNode {
+        op: remap_variable(node.op, dropped),
+        left: node.left.as_ref().map(|left| remap_node(left, dropped)),
+        right: node.right.as_ref().map(|right| remap_node(right, dropped)),
+    });
+}
+
+/// Input combinations a formula leaves unspecified, see `split_dont_cares`
+#[derive(Clone, Debug, PartialEq)]
+pub enum DontCares
+{
+    /// The rows where this formula is true
+    Condition(String),
+    /// Minterm indices (see `RowOrdering::minterm_at`)
+    Minterms(Vec<u32>),
+}
+
+/// Whether `keyword` at byte `pos` of `source` starts a word
+fn starts_word(source: &str, pos: usize) -> bool
+{
+    return source[..pos].chars().next_back().is_none_or(|ch| !ch.is_alphanumeric() && ch != '_');
+}
+
+/**
+ * Splits a trailing don't-care clause off `source`, either `dc: <formula>` or a minterm list
+ * `d(3, 7)`. Neither can be part of a formula, a variable is never followed by `:` or `(`
+**/
+pub fn split_dont_cares(source: &str) -> Result<(&str, Option<DontCares>), FormulaError>
+{
+    if let Some(pos) = source.find("dc:").filter(|pos| starts_word(source, *pos))
+    {
+        return Ok((&source[..pos], Some(DontCares::Condition(String::from(&source[pos + 3..])))));
+    }
+
+    let trimmed = source.trim_end();
+
+    if let Some(pos) = trimmed.rfind("d(").filter(|pos| starts_word(trimmed, *pos) && trimmed.ends_with(')'))
+    {
+        let list = &trimmed[pos + 2..trimmed.len() - 1];
+
+        if list.chars().all(|ch| ch.is_ascii_digit() || ch == ',' || ch.is_whitespace())
+        {
+            let minterms = list.split(',')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty())
+                .map(|entry| entry.parse::<u32>().map_err(|_| FormulaError::InvalidDontCare(String::from(entry))))
+                .collect::<Result<Vec<u32>, FormulaError>>()?;
+
+            return Ok((&trimmed[..pos], Some(DontCares::Minterms(minterms))));
+        }
+    }
+
+    return Ok((source, None));
+}
+
+/**
+ * Parses several formulas over a single variable list: every returned formula has the same
+ * `variables`, ordered by first appearance across all sources, so their groups can be
+ * evaluated against the same assignment
+**/
+pub fn parse_shared(sources: &[&str]) -> Result<Vec<Formula>, FormulaError>
+{
+    let mut formulas = Vec::<Formula>::with_capacity(sources.len());
+    let mut variables = Vec::<String>::new();
+
+    for source in sources
+    {
+        let formula = Formula::parse_with_variables(source, variables)?;
+        variables = formula.variables.clone();
+        formulas.push(formula);
+    }
+
+    // Earlier formulas only know a prefix of the final list, which leaves their locations valid
+    for formula in formulas.iter_mut()
+    {
+        formula.variables = variables.clone();
+    }
+
+    return Ok(formulas);
+}
+
+/**
+ * Parses a whitespace separated list of `name=value` entries, e.g. `p=T q=false r=1`. Values are
+ * written like literals in formulas (`T`, `F`, `true`, `false`, any case) or as `1` and `0`
+**/
+pub fn parse_assignment(source: &str) -> Result<Vec<(String, bool)>, FormulaError>
+{
+    let mut assignment = Vec::<(String, bool)>::new();
+
+    for entry in source.split_whitespace()
+    {
+        let invalid = || FormulaError::InvalidAssignment(String::from(entry));
+
+        let (name, value) = entry.split_once('=').ok_or_else(invalid)?;
+
+        let name_tokens = scanner::tokenize(name);
+        if name_tokens.len() != 2 || name_tokens[0].token_type != TokenType::Variable
+        {
+            return Err(invalid());
+        }
+
+        let value = match value.to_ascii_lowercase().as_str()
+        {
+            "true" | "t" | "1" => true,
+            "false" | "f" | "0" => false,
+            _ => { return Err(invalid()); }
+        };
+
+        assignment.push((String::from(name), value));
+    }
+
+    return Ok(assignment);
+}
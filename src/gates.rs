@@ -0,0 +1,430 @@
+use crate::compiler::{ASTNode, NodeOperation};
+use crate::post::Connective;
+use crate::scanner::OperatorType;
+use std::collections::{BTreeMap, HashMap};
+
+/// Expressions with more nodes than this, written out as a tree, are not rendered
+pub const MAX_RENDERED_NODES: u128 = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GateError
+{
+    /// The basis cannot build this connective of the formula, or this constant
+    Inexpressible(Connective),
+}
+
+impl std::fmt::Display for GateError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self
+        {
+            GateError::Inexpressible(connective) => write!(f, "{} cannot be built from the basis", connective.symbol()),
+        }
+    }
+}
+
+impl std::error::Error for GateError {}
+
+/**
+ * A connective expressed in terms of a basis, over two placeholders `Arg(0)` and `Arg(1)` for its
+ * operands. Unary connectives and constants leave the second (and first) placeholder unused
+**/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Template
+{
+    Arg(usize),
+    Apply(Connective, Vec<Template>),
+}
+
+impl Template
+{
+    /// The placeholder the template refers to first, reading from the left
+    fn first_arg(&self) -> Option<usize>
+    {
+        return match self
+        {
+            Template::Arg(index) => Some(*index),
+            Template::Apply(_, args) => args.iter().find_map(Template::first_arg),
+        };
+    }
+
+    /// Number of connectives with operands, constants are not gates
+    fn gate_count(&self) -> usize
+    {
+        return match self
+        {
+            Template::Arg(_) => 0,
+            Template::Apply(connective, args) => {
+                (connective.arity() > 0) as usize + args.iter().map(Template::gate_count).sum::<usize>()
+            },
+        };
+    }
+}
+
+/// Truth table of a connective over the placeholders, bit `2x + y` holds its value for `x` and `y`
+fn placeholder_truth(connective: Connective) -> u8
+{
+    return (0..4u8)
+        .filter(|row| {
+            let args = [row & 2 != 0, row & 1 != 0];
+            connective.apply(&args[..connective.arity() as usize]).expect("Arguments are as many as the arity")
+        })
+        .fold(0, |acc, row| acc | 1 << row);
+}
+
+/**
+ * The smallest expression over `basis` found for every function of two placeholders the basis can
+ * build, keyed by their truth table (see `placeholder_truth`). Grows expressions by applying every
+ * connective to the best ones known so far until none of them improves
+**/
+pub fn templates(basis: &[Connective]) -> BTreeMap<u8, Template>
+{
+    let mut best = BTreeMap::<u8, Template>::new();
+    best.insert(0b1100, Template::Arg(0));
+    best.insert(0b1010, Template::Arg(1));
+
+    loop
+    {
+        // Cheapest first and the first placeholder before the second, so ties keep the simplest form
+        let mut known = best.iter().map(|(truth, template)| (*truth, template.clone())).collect::<Vec<(u8, Template)>>();
+        known.sort_by_key(|(truth, template)| (template.gate_count(), template.first_arg(), *truth));
+        let mut improved = false;
+
+        for connective in basis
+        {
+            let args: Vec<Vec<&(u8, Template)>> = match connective.arity()
+            {
+                0 => vec![vec![]],
+                1 => known.iter().map(|entry| vec![entry]).collect(),
+                _ => known.iter().flat_map(|left| known.iter().map(move |right| vec![left, right])).collect(),
+            };
+
+            for args in args
+            {
+                let truth = (0..4u8)
+                    .filter(|row| connective.apply(&args.iter().map(|(truth, _)| *truth >> row & 1 == 1).collect::<Vec<bool>>()).expect("Arguments are as many as the arity"))
+                    .fold(0, |acc, row| acc | 1 << row);
+
+                let candidate = Template::Apply(*connective, args.iter().map(|(_, template)| template.clone()).collect());
+
+                if best.get(&truth).is_none_or(|current| candidate.gate_count() < current.gate_count())
+                {
+                    best.insert(truth, candidate);
+                    improved = true;
+                }
+            }
+        }
+
+        if !improved { return best; }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum GateNode
+{
+    Input(usize),
+    Gate(Connective, Vec<usize>),
+}
+
+/**
+ * A circuit over a fixed basis. Identical gates with identical inputs are stored once, the way
+ * they would be wired once, so the gate count of a circuit counts shared subexpressions once
+**/
+pub struct Circuit
+{
+    nodes: Vec<GateNode>,
+    unique: HashMap<GateNode, usize>,
+}
+
+impl Circuit
+{
+    fn add(&mut self, node: GateNode) -> usize
+    {
+        if let Some(index) = self.unique.get(&node) { return *index; }
+
+        self.nodes.push(node.clone());
+        self.unique.insert(node, self.nodes.len() - 1);
+        return self.nodes.len() - 1;
+    }
+
+    fn reachable(&self, root: usize) -> Vec<usize>
+    {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut stack = vec![root];
+        let mut reached = Vec::<usize>::new();
+
+        while let Some(node) = stack.pop()
+        {
+            if seen[node] { continue; }
+            seen[node] = true;
+            reached.push(node);
+
+            if let GateNode::Gate(_, operands) = &self.nodes[node]
+            {
+                stack.extend(operands.iter());
+            }
+        }
+
+        return reached;
+    }
+
+    /// Number of gates of every connective reachable from `root`, constants left out
+    pub fn gate_counts(&self, root: usize) -> BTreeMap<&'static str, usize>
+    {
+        let mut counts = BTreeMap::<&'static str, usize>::new();
+
+        for node in self.reachable(root)
+        {
+            if let GateNode::Gate(connective, operands) = &self.nodes[node]
+            {
+                if !operands.is_empty() { *counts.entry(connective.symbol()).or_default() += 1; }
+            }
+        }
+
+        return counts;
+    }
+
+    /// Nodes of the expression rooted at `root` written out as a tree, without sharing. Saturates
+    pub fn tree_size(&self, root: usize) -> u128
+    {
+        let mut memo = HashMap::<usize, u128>::new();
+        return self.tree_size_impl(root, &mut memo);
+    }
+
+    fn tree_size_impl(&self, node: usize, memo: &mut HashMap<usize, u128>) -> u128
+    {
+        if let Some(size) = memo.get(&node) { return *size; }
+
+        let size = match &self.nodes[node]
+        {
+            GateNode::Input(_) => 1,
+            GateNode::Gate(_, operands) => operands.iter()
+                .fold(1u128, |acc, operand| acc.saturating_add(self.tree_size_impl(*operand, memo))),
+        };
+
+        memo.insert(node, size);
+        return size;
+    }
+
+    /// Value of the circuit for the values of the variables, indexed by location
+    pub fn evaluate(&self, root: usize, values: &[bool]) -> bool
+    {
+        return match &self.nodes[root]
+        {
+            GateNode::Input(loc) => values[*loc],
+            GateNode::Gate(connective, operands) => {
+                // Gates are only added by `instantiate`, with as many operands as their connective takes
+                connective.apply(&operands.iter().map(|operand| self.evaluate(*operand, values)).collect::<Vec<bool>>())
+                    .expect("Arguments are as many as the arity")
+            },
+        };
+    }
+
+    /// Writes the expression out, binary gates in parentheses with the symbol of their connective between the operands
+    pub fn render(&self, root: usize, variables: &[String]) -> String
+    {
+        return match &self.nodes[root]
+        {
+            GateNode::Input(loc) => variables[*loc].clone(),
+            GateNode::Gate(connective, operands) => match operands[..]
+            {
+                [] => String::from(connective.symbol()),
+                [operand] => format!("{}{}", connective.symbol(), self.render(operand, variables)),
+                _ => format!(
+                    "({})",
+                    operands.iter().map(|operand| self.render(*operand, variables)).collect::<Vec<String>>()
+                        .join(&format!(" {} ", connective.symbol()))
+                ),
+            },
+        };
+    }
+}
+
+/// A subtree after rewriting, constants are folded away as long as possible
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Rewritten
+{
+    Constant(bool),
+    Node(usize),
+}
+
+/**
+ * Matches `template` against `pattern`, a template over the first placeholder only, and returns what
+ * that placeholder stands for. Every occurrence of it must stand for the same subtemplate
+**/
+fn match_pattern<'a>(pattern: &Template, template: &'a Template, bound: &mut Option<&'a Template>) -> bool
+{
+    return match (pattern, template)
+    {
+        (Template::Arg(_), _) => match bound
+        {
+            Some(previous) => *previous == template,
+            None => {
+                *bound = Some(template);
+                true
+            },
+        },
+        (Template::Apply(pattern_connective, patterns), Template::Apply(connective, args)) => {
+            pattern_connective == connective
+                && patterns.len() == args.len()
+                && patterns.iter().zip(args.iter()).all(|(pattern, arg)| match_pattern(pattern, arg, bound))
+        },
+        _ => false,
+    };
+}
+
+struct Rewriter
+{
+    circuit: Circuit,
+    templates: BTreeMap<u8, Template>,
+    /// The template of NOT, if the basis can negate
+    negation: Option<Template>,
+    /// Pairs of nodes known to be each other's negation, so negating twice gives the node back
+    negated: HashMap<usize, usize>,
+    /// The input templates of constants that need an operand are built on
+    witness: usize,
+}
+
+impl Rewriter
+{
+    fn new(basis: &[Connective]) -> Self
+    {
+        let templates = templates(basis);
+        let negation = templates.get(&placeholder_truth(Connective::Not)).cloned();
+
+        let mut circuit = Circuit { nodes: vec![], unique: HashMap::new() };
+        let witness = circuit.add(GateNode::Input(0));
+
+        Self { circuit, templates, negation, negated: HashMap::new(), witness }
+    }
+
+    fn negate(&mut self, node: usize) -> Result<usize, GateError>
+    {
+        if let Some(negation) = self.negated.get(&node) { return Ok(*negation); }
+
+        // NOT is not the identity, so its template always applies a connective
+        let Some(Template::Apply(connective, operands)) = self.negation.clone() else {
+            return Err(GateError::Inexpressible(Connective::Not));
+        };
+
+        let operands = operands.iter().map(|operand| self.instantiate(operand, [node, node])).collect();
+        let negation = self.circuit.add(GateNode::Gate(connective, operands));
+
+        self.negated.insert(node, negation);
+        self.negated.insert(negation, node);
+        return Ok(negation);
+    }
+
+    /// Builds a template on the given nodes, negations of any part of it go through `negate`
+    fn instantiate(&mut self, template: &Template, args: [usize; 2]) -> usize
+    {
+        let mut negated = None;
+        if self.negation.as_ref().is_some_and(|negation| match_pattern(negation, template, &mut negated))
+        {
+            let operand = self.instantiate(negated.unwrap(), args);
+            return self.negate(operand).expect("Negation template not found");
+        }
+
+        return match template
+        {
+            Template::Arg(index) => args[*index],
+            Template::Apply(connective, operands) => {
+                let operands = operands.iter().map(|operand| self.instantiate(operand, args)).collect();
+                self.circuit.add(GateNode::Gate(*connective, operands))
+            },
+        };
+    }
+
+    fn apply(&mut self, connective: Connective, args: [usize; 2]) -> Result<usize, GateError>
+    {
+        if connective == Connective::Not { return self.negate(args[0]); }
+
+        let template = self.templates.get(&placeholder_truth(connective)).ok_or(GateError::Inexpressible(connective))?;
+        let template = template.clone();
+
+        return Ok(self.instantiate(&template, args));
+    }
+
+    fn constant(&mut self, val: bool) -> Result<usize, GateError>
+    {
+        let connective = if val { Connective::True } else { Connective::False };
+        let template = self.templates.get(&placeholder_truth(connective)).ok_or(GateError::Inexpressible(connective))?;
+        let template = template.clone();
+
+        // Bases without the constant build it from an input, such as `(x nand (x nand x))`
+        return Ok(self.instantiate(&template, [self.witness, self.witness]));
+    }
+
+    /// The binary connective applied with one operand fixed, either constant, identity or negation of the other
+    fn partial(&mut self, op: Connective, fixed: bool, fixed_left: bool, other: usize) -> Result<Rewritten, GateError>
+    {
+        let args = |free: bool| if fixed_left { [fixed, free] } else { [free, fixed] };
+        let value = |free: bool| op.apply(&args(free)).expect("Arguments are as many as the arity");
+
+        return match (value(false), value(true))
+        {
+            (false, true) => Ok(Rewritten::Node(other)),
+            (true, false) => Ok(Rewritten::Node(self.apply(Connective::Not, [other, other])?)),
+            (val, _) => Ok(Rewritten::Constant(val)),
+        };
+    }
+
+    fn rewrite(&mut self, node: &ASTNode) -> Result<Rewritten, GateError>
+    {
+        let operand = |right: bool| {
+            let child = if right { &node.right } else { &node.left };
+            child.as_ref().expect("Operand not found")
+        };
+
+        return match node.op
+        {
+            NodeOperation::VariableDeref(loc) => Ok(Rewritten::Node(self.circuit.add(GateNode::Input(loc)))),
+            NodeOperation::Literal(val) => Ok(Rewritten::Constant(val)),
+            NodeOperation::Subexpression | NodeOperation::IndexedSubexpression(_) => self.rewrite(operand(false)),
+            NodeOperation::UnaryOperation(_) => match self.rewrite(operand(false))?
+            {
+                Rewritten::Constant(val) => Ok(Rewritten::Constant(!val)),
+                Rewritten::Node(index) => Ok(Rewritten::Node(self.apply(Connective::Not, [index, index])?)),
+            },
+            NodeOperation::BinaryOperation(op) => {
+                let connective = match op
+                {
+                    OperatorType::AND => Connective::And,
+                    OperatorType::OR => Connective::Or,
+                    OperatorType::CNDL => Connective::Implies,
+                    OperatorType::BI_CNDL => Connective::Iff,
+                    OperatorType::NOT => Connective::Not,
+                };
+
+                match (self.rewrite(operand(false))?, self.rewrite(operand(true))?)
+                {
+                    (Rewritten::Constant(left), Rewritten::Constant(right)) => Ok(Rewritten::Constant(connective.apply(&[left, right]).expect("Arguments are as many as the arity"))),
+                    (Rewritten::Constant(left), Rewritten::Node(right)) => self.partial(connective, left, true, right),
+                    (Rewritten::Node(left), Rewritten::Constant(right)) => self.partial(connective, right, false, left),
+                    (Rewritten::Node(left), Rewritten::Node(right)) => Ok(Rewritten::Node(self.apply(connective, [left, right])?)),
+                }
+            },
+        };
+    }
+}
+
+/**
+ * Rewrites a syntax tree into an equivalent circuit that only uses the connectives of `basis`.
+ * Constants are folded into their neighbours first, so they only need to be built when the whole
+ * formula is constant. A basis without the constant builds it from the input at location 0, the
+ * first variable, or a fresh input for a formula without variables. Fails when the basis cannot
+ * express a connective the formula needs, which may happen for bases that are not functionally complete
+**/
+pub fn rewrite(node: &ASTNode, basis: &[Connective]) -> Result<(Circuit, usize), GateError>
+{
+    let mut rewriter = Rewriter::new(basis);
+
+    let root = match rewriter.rewrite(node)?
+    {
+        Rewritten::Node(index) => index,
+        Rewritten::Constant(val) => rewriter.constant(val)?,
+    };
+
+    return Ok((rewriter.circuit, root));
+}
@@ -0,0 +1,51 @@
+/// Largest number of variables a Karnaugh map is drawn for, an 8 by 8 grid
+pub const MAX_VARIABLES: u32 = 6;
+
+/// Reflected Gray code of `bits` bits: consecutive codes, and the last and the first, differ in one bit
+pub fn gray_codes(bits: u32) -> Vec<u32>
+{
+    return (0..1u32 << bits).map(|index| index ^ (index >> 1)).collect();
+}
+
+/// Renders the `bits` lowest bits of `code`, most significant first
+pub fn label(code: u32, bits: u32) -> String
+{
+    return (0..bits).rev().map(|bit| if code >> bit & 1 == 1 { '1' } else { '0' }).collect();
+}
+
+/**
+ * Placement of the rows of a truth table in a Karnaugh map. The first `row_bits` variables
+ * select the row and the others the column, both counting in Gray code so that neighbouring
+ * cells, wrapping around the edges, differ in a single variable
+**/
+#[derive(Clone, Debug, PartialEq)]
+pub struct KarnaughMap
+{
+    pub row_bits: u32,
+    pub column_bits: u32,
+    pub rows: Vec<u32>,
+    pub columns: Vec<u32>,
+}
+
+impl KarnaughMap
+{
+    /// Layout for `size` variables, with one column variable more than row variables for odd sizes
+    pub fn new(size: u32) -> Self
+    {
+        let row_bits = size / 2;
+        let column_bits = size - row_bits;
+
+        Self {
+            row_bits,
+            column_bits,
+            rows: gray_codes(row_bits),
+            columns: gray_codes(column_bits),
+        }
+    }
+
+    /// Minterm index (see `RowOrdering::minterm_at`) of the cell at `row` and `column`
+    pub fn minterm(&self, row: usize, column: usize) -> u32
+    {
+        return self.rows[row] << self.column_bits | self.columns[column];
+    }
+}
@@ -0,0 +1,22 @@
+// Functions end with an explicit `return`, which is the style throughout
+#![allow(clippy::needless_return)]
+
+pub mod scanner;
+pub mod compiler;
+pub mod execution;
+pub mod bytecode;
+pub mod table;
+pub mod formula;
+pub mod analysis;
+pub mod cnf;
+pub mod sat;
+pub mod canonical;
+pub mod transform;
+pub mod minimize;
+pub mod espresso;
+pub mod kmap;
+pub mod bdd;
+pub mod anf;
+pub mod post;
+pub mod gates;
+pub mod simplify;
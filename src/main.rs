@@ -1,141 +1,1429 @@
-#![allow(unused_imports)]
-#![allow(unused_variables)]
-#![allow(dead_code)]
-#![allow(unreachable_code)]
+// Functions end with an explicit `return`, which is the style throughout
+#![allow(clippy::needless_return)]
 
-#![allow(unused_mut)]
-
-pub mod scanner;
-pub mod compiler;
-pub mod execution;
+use ttbl::{analysis, anf, bdd, canonical, cnf, espresso, execution, formula, gates, kmap, minimize, post, sat, simplify, table, transform};
+use ttbl::analysis::RowKind;
+use ttbl::compiler::ASTNode;
+use ttbl::formula::{DontCares, Formula, FormulaError};
+use ttbl::minimize::{Implicant, MinimizeError};
+use ttbl::scanner::OperatorType;
+use ttbl::table::{RowOrder, RowOrdering};
 
 use std::iter::Iterator;
 use std::{
-    io::{self, Write},
-    iter::Scan,
+    env,
+    error::Error,
+    fs,
+    process,
+    thread,
 };
-use tabled::{self, Table, Modify, Wrap, Style, Concat, builder::Builder};
+use tabled::{Modify, Wrap, Style, Concat, builder::Builder};
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
 
-fn pow2(exponent: u32) -> u32
+struct Settings
+{
+    /// Maximum number of worker threads used to evaluate a table
+    threads: usize,
+    /// Order of the rows in every printed table
+    ordering: RowOrdering,
+    /// Leave variables no column depends on out of printed tables
+    drop_vacuous: bool,
+}
+
+impl Settings
+{
+    fn from_args() -> Result<Self, String>
+    {
+        let mut settings = Settings {
+            threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            ordering: RowOrdering::default(),
+            drop_vacuous: false,
+        };
+
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next()
+        {
+            match arg.as_str()
+            {
+                "-j" | "--threads" => {
+                    settings.threads = args.next()
+                        .and_then(|val| val.parse::<usize>().ok())
+                        .filter(|val| *val > 0)
+                        .ok_or(format!("{} expects a positive number", arg))?;
+                },
+                "--order" => {
+                    settings.ordering.order = match args.next().as_deref()
+                    {
+                        Some("binary") => RowOrder::Binary,
+                        Some("true-first") => RowOrder::AllTrueFirst,
+                        Some("gray") => RowOrder::Gray,
+                        _ => { return Err(format!("{} expects one of: binary, true-first, gray", arg)); }
+                    };
+                },
+                "--reverse" => { settings.ordering.reversed = true; },
+                "--drop-vacuous" => { settings.drop_vacuous = true; },
+                _ => { return Err(format!("Unknown argument: {}", arg)); }
+            }
+        }
+
+        return Ok(settings);
+    }
+}
+
+fn map_bool_cell(val: &bool) -> &'static str
+{
+    if *val { "T" } else { "F" }
+}
+
+/// Cell of a result column on a don't-care row
+const DONT_CARE_CELL: &str = "X";
+
+fn render_table(variables: &[String], reprs: &[String], inputs: &[Vec<bool>], outputs: &[Vec<bool>]) -> String
+{
+    return render_annotated_table(variables, reprs, inputs, outputs, None);
+}
+
+/// Same as `render_table` with an extra column of free text, given as its header and one cell per row
+fn render_annotated_table(
+    variables: &[String],
+    reprs: &[String],
+    inputs: &[Vec<bool>],
+    outputs: &[Vec<bool>],
+    notes: Option<(&str, &[&str])>
+) -> String
+{
+    let cells = outputs.iter()
+        .map(|row| row.iter().map(map_bool_cell).collect())
+        .collect::<Vec<Vec<&'static str>>>();
+
+    return render_table_cells(variables, reprs, inputs, &cells, notes);
+}
+
+/// Same as `render_annotated_table` with the result cells given as text
+fn render_table_cells(
+    variables: &[String],
+    reprs: &[String],
+    inputs: &[Vec<bool>],
+    outputs: &[Vec<&'static str>],
+    notes: Option<(&str, &[&str])>
+) -> String
+{
+    let mut builder_input = Builder::default().set_header(variables);
+    let mut builder_output = Builder::default().set_header(reprs);
+
+    for (inputs, outputs) in inputs.iter().zip(outputs.iter())
+    {
+        let table_row_input = inputs
+                .iter()
+                .map(map_bool_cell)
+                .collect::<Vec<&'static str>>();
+
+        builder_input = builder_input.add_row(table_row_input);
+        builder_output = builder_output.add_row(outputs.clone());
+    }
+
+    let style = Style::modern();    
+
+    let table_input = builder_input
+                    .build()
+                    .with(style.clone());
+
+    let table_output = builder_output
+                    .build()
+                    .with(Modify::new(tabled::Full).with(Wrap::new(20)))
+                    .with(style.clone());
+
+    let mut display_table = table_input.with(Concat::horizontal(table_output));
+
+    if let Some((header, cells)) = notes
+    {
+        let mut builder_notes = Builder::default().set_header([header]);
+        for cell in cells
+        {
+            builder_notes = builder_notes.add_row([cell]);
+        }
+
+        display_table = display_table.with(Concat::horizontal(builder_notes.build().with(style.clone())));
+    }
+
+    return display_table.to_string();
+}
+
+type CommandResult = Result<(), Box<dyn Error>>;
+
+/// Don't-care rows of a table indexed by minterm, see `Formula::dont_care_vector`
+type DontCareRows = Vec<bool>;
+
+/**
+ * Parses a formula followed by an optional don't-care clause (see `formula::split_dont_cares`),
+ * returning the don't-care rows indexed by minterm when there is one
+**/
+fn parse_with_dont_cares(source: &str, settings: &Settings) -> Result<(Formula, Option<DontCareRows>), Box<dyn Error>>
+{
+    let (source, dont_cares) = formula::split_dont_cares(source)?;
+    let formula = Formula::parse(source)?;
+
+    if formula.variables.len() > MAX_TABULATED_VARIABLES
+    {
+        return Err(format!(
+            "Tables are printed for at most {} variables, use sat, count or bdd for wider formulas", MAX_TABULATED_VARIABLES
+        ).into());
+    }
+
+    let dont_care = match dont_cares
+    {
+        Some(dont_cares) => Some(formula.dont_care_vector(&dont_cares, settings.threads)?),
+        None => None,
+    };
+
+    return Ok((formula, dont_care));
+}
+
+/**
+ * Prints the full truth table of a formula. Don't-care rows are shown as `X` in the last column
+ * and left out of its classification
+**/
+fn command_table(source: &str, settings: &Settings) -> CommandResult
+{
+    let (formula, dont_care) = parse_with_dont_cares(source, settings)?;
+    let size = formula.variables.len() as u32;
+    let table = table::evaluate_table(&formula.groups, size, settings.ordering, settings.threads)?;
+    let mut summaries = analysis::classify_table(&table);
+
+    let Some(dont_care) = dont_care else {
+        let depends = analysis::dependencies(&table);
+        let names = |flags: &[bool]| {
+            (0..flags.len()).filter(|loc| flags[*loc]).map(|loc| formula.variables[loc].as_str()).collect::<Vec<&str>>().join(", ")
+        };
+
+        // Variables of the result that only subexpression columns depend on stay in the table
+        let vacuous = depends[depends.len() - 1].iter().map(|depends| !depends).collect::<Vec<bool>>();
+        let unused = (0..size as usize).map(|loc| depends.iter().all(|col| !col[loc])).collect::<Vec<bool>>();
+        let drop = settings.drop_vacuous && unused.contains(&true);
+
+        let reduced = if drop { Some(formula.without_variables(&unused)) } else { None };
+        let shown = reduced.as_ref().unwrap_or(&formula);
+        let table = match drop
+        {
+            true => table::evaluate_table(&shown.groups, shown.variables.len() as u32, settings.ordering, settings.threads)?,
+            false => table,
+        };
+
+        println!("{}", render_table(&shown.variables, &shown.reprs, &table.inputs, &table.outputs));
+
+        for (repr, summary) in shown.reprs.iter().zip(analysis::classify_table(&table))
+        {
+            println!("{}: {}", repr, summary);
+        }
+
+        if !shown.variables.is_empty()
+        {
+            let sensitivity = analysis::sensitivity(&table.result_vector(), shown.variables.len() as u32);
+            println!("{}", format_influence(&shown.variables, &sensitivity));
+        }
+
+        if drop { println!("Dropped vacuous variable(s): {}", names(&unused)); }
+
+        let kept = (0..vacuous.len()).map(|loc| vacuous[loc] && !(drop && unused[loc])).collect::<Vec<bool>>();
+        if kept.contains(&true) { println!("Vacuous variable(s): {}", names(&kept)); }
+        println!();
+
+        return Ok(());
+    };
+
+    let free = (0..table.outputs.len() as u32)
+        .map(|position| dont_care[settings.ordering.minterm_at(size, position) as usize])
+        .collect::<Vec<bool>>();
+
+    let cells = table.outputs.iter().zip(free.iter())
+        .map(|(row, free)| {
+            let mut cells = row.iter().map(map_bool_cell).collect::<Vec<&'static str>>();
+            if *free { cells[row.len() - 1] = DONT_CARE_CELL; }
+            cells
+        })
+        .collect::<Vec<Vec<&'static str>>>();
+
+    let last = summaries.len() - 1;
+    summaries[last] = analysis::classify(table.outputs.iter().zip(free.iter()).filter(|(_, free)| !**free).map(|(row, _)| row[last]));
+
+    println!("{}", render_table_cells(&formula.variables, &formula.reprs, &table.inputs, &cells, None));
+
+    for (repr, summary) in formula.reprs.iter().zip(summaries)
+    {
+        println!("{}: {}", repr, summary);
+    }
+
+    // Don't-care rows keep the value the formula gives them
+    if size > 0
+    {
+        let sensitivity = analysis::sensitivity(&table.result_vector(), size);
+        println!("{}", format_influence(&formula.variables, &sensitivity));
+    }
+    println!("{} don't-care row(s)\n", free.iter().filter(|free| **free).count());
+
+    return Ok(());
+}
+
+/// `eval p=T q=F : <formula>`, prints the row of the table for a single assignment
+fn command_eval(args: &str) -> CommandResult
+{
+    let (assignment, source) = args.split_once(':').ok_or("Usage: eval p=T q=F : <formula>")?;
+
+    let assignment = formula::parse_assignment(assignment)?;
+    let formula = Formula::parse(source)?;
+    let outputs = formula.evaluate_assignment(&assignment)?;
+
+    let inputs = formula.variables.iter()
+        .map(|var| assignment.iter().rev().find(|(name, _)| name == var).map(|(_, value)| *value).unwrap_or(false))
+        .collect::<Vec<bool>>();
+
+    println!("{}", render_table(&formula.variables, &formula.reprs, &[inputs], &[outputs]));
+    return Ok(());
+}
+
+/// Formulas with more variables than this are checked with the SAT solver instead of a table
+const MAX_TABULATED_VARIABLES: usize = 16;
+
+fn format_assignment(variables: &[String], values: &[bool]) -> String
+{
+    return variables.iter()
+        .zip(values.iter())
+        .map(|(var, val)| format!("{}={}", var, map_bool_cell(val)))
+        .collect::<Vec<String>>()
+        .join(" ");
+}
+
+/// `sat <formula>`, finds an assignment making the formula true
+fn command_sat(args: &str) -> CommandResult
+{
+    let formula = Formula::parse(args)?;
+
+    match sat::find_model(&formula)
+    {
+        Some(model) => println!("Satisfiable: {}\n", format_assignment(&formula.variables, &model)),
+        None => println!("Unsatisfiable\n"),
+    }
+
+    return Ok(());
+}
+
+/// `valid <formula>`, checks that the formula is true for every assignment
+fn command_valid(args: &str) -> CommandResult
+{
+    let formula = Formula::parse(args)?;
+
+    match sat::find_counterexample(&formula)
+    {
+        Some(model) => println!("Not valid, counterexample: {}\n", format_assignment(&formula.variables, &model)),
+        None => println!("Valid\n"),
+    }
+
+    return Ok(());
+}
+
+/**
+ * `count <formula>`, counts the assignments that make the formula true. Up to
+ * `analysis::MAX_COUNTED_VARIABLES` rows are evaluated, wider formulas are counted on their BDD
+**/
+fn command_count(args: &str, settings: &Settings) -> CommandResult
 {
-    return (2 as u32).pow(exponent);
+    let formula = Formula::parse(args)?;
+    let size = formula.variables.len();
+
+    if size <= analysis::MAX_COUNTED_VARIABLES
+    {
+        let count = analysis::count_models(&formula, settings.threads)?;
+        println!("{} of {} assignment(s) satisfy the formula\n", count, 1u64 << size);
+        return Ok(());
+    }
+
+    // Too many rows to evaluate one by one, the BDD counts them per node instead
+    let mut bdd = bdd::Bdd::with_natural_order(size, bdd::DEFAULT_NODE_LIMIT);
+    let root = bdd.from_node(&formula.root)?;
+    let count = bdd.count_models(root);
+
+    match count
+    {
+        u128::MAX => println!("At least 2^128 of 2^{} assignment(s) satisfy the formula\n", size),
+        _ => println!("{} of 2^{} assignment(s) satisfy the formula\n", count, size),
+    }
+    return Ok(());
+}
+
+/**
+ * `models [limit=N] [on=p,q] : <formula>` or `models <formula>`, lists the assignments that make
+ * the formula true, optionally only the first `N` and only the values of the given variables
+**/
+fn command_models(args: &str) -> CommandResult
+{
+    let (options, source) = args.split_once(':').unwrap_or(("", args));
+    let formula = Formula::parse(source)?;
+
+    let mut limit: Option<usize> = None;
+    let mut projection: Vec<usize> = (0..formula.variables.len()).collect();
+
+    for option in options.split_whitespace()
+    {
+        match option.split_once('=')
+        {
+            Some(("limit", val)) => {
+                limit = Some(val.parse::<usize>().map_err(|_| format!("Invalid limit \"{}\"", val))?);
+            },
+            Some(("on", names)) => {
+                projection = names.split(',')
+                    .filter(|name| !name.is_empty())
+                    .map(|name| {
+                        formula.variables.iter().position(|var| var == name)
+                            .ok_or(FormulaError::UnknownVariable(String::from(name)))
+                    })
+                    .collect::<Result<Vec<usize>, FormulaError>>()?;
+            },
+            _ => { return Err(format!("Unknown option \"{}\", expected limit=N or on=p,q", option).into()); }
+        }
+    }
+
+    let names = projection.iter().map(|loc| formula.variables[*loc].clone()).collect::<Vec<String>>();
+    let models = sat::enumerate_models(&formula, &projection, limit);
+
+    for model in &models
+    {
+        println!("{}", format_assignment(&names, model));
+    }
+
+    println!("{} model(s){}\n", models.len(), if limit == Some(models.len()) { " (limit reached)" } else { "" });
+    return Ok(());
 }
 
-struct BoolPermutationsIterator
+/// Letter naming the `index`-th group of a Karnaugh map cover
+fn group_letter(index: usize) -> char
 {
-    data: Vec<bool>,
-    pub size: u32,
-    current: u32,
-    last: u32,
+    const LETTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    return LETTERS[index % LETTERS.len()] as char;
 }
 
-impl BoolPermutationsIterator
+/**
+ * `kmap <formula>` or `kmap groups : <formula>`, draws the last column of the table as a Karnaugh
+ * map, optionally followed by a don't-care clause. With `groups` every cell also lists the letters
+ * of the products of a minimal sum of products (see `minimize`) that cover it
+**/
+fn command_kmap(args: &str, settings: &Settings) -> CommandResult
 {
-    pub fn new(size: u32) -> Self
+    let (show_groups, source) = match args.split_once(':')
+    {
+        Some((option, source)) if option.trim() == "groups" => (true, source),
+        _ => (false, args),
+    };
+
+    let (formula, dont_care) = parse_with_dont_cares(source, settings)?;
+    let size = formula.variables.len() as u32;
+
+    if size > kmap::MAX_VARIABLES
+    {
+        return Err(format!("Karnaugh maps are drawn for at most {} variables", kmap::MAX_VARIABLES).into());
+    }
+
+    let truth = table::truth_vector(&formula.groups, size, settings.threads)?;
+    let dont_care = dont_care.unwrap_or_default();
+    let free = |minterm: u32| dont_care.get(minterm as usize) == Some(&true);
+
+    let groups = match show_groups
+    {
+        true => minimize::minimal_sum_of_products(size, &truth, &dont_care, minimize::DEFAULT_COVER_LIMIT)?,
+        false => vec![],
+    };
+    let cubes = groups.iter().map(|term| Implicant::from_term(size, term)).collect::<Vec<Implicant>>();
+
+    let map = kmap::KarnaughMap::new(size);
+    let (row_names, column_names) = formula.variables.split_at(map.row_bits as usize);
+
+    let mut header = vec![format!("{} \\ {}", row_names.join(""), column_names.join(""))];
+    header.extend(map.columns.iter().map(|code| kmap::label(*code, map.column_bits)));
+    let mut builder = Builder::default().set_header(header);
+
+    for row in 0..map.rows.len()
     {
-        Self {
-            data: vec![false; size as usize],
-            size,
-            current: 0,
-            last: pow2(size)
+        let mut cells = vec![kmap::label(map.rows[row], map.row_bits)];
+
+        for column in 0..map.columns.len()
+        {
+            let minterm = map.minterm(row, column);
+            let value = match (free(minterm), truth[minterm as usize])
+            {
+                (true, _) => DONT_CARE_CELL,
+                (false, true) => "1",
+                (false, false) => "0",
+            };
+
+            let letters = (0..cubes.len())
+                .filter(|index| cubes[*index].covers(minterm))
+                .map(group_letter)
+                .collect::<String>();
+
+            cells.push(if letters.is_empty() { String::from(value) } else { format!("{} {}", value, letters) });
         }
+
+        builder = builder.add_row(cells);
     }
 
-    pub fn get(&self) -> &Vec<bool>
+    println!("{}", builder.build().with(Style::modern()));
+
+    for (index, term) in groups.iter().enumerate()
     {
-        return &self.data;
+        println!("{}: {}", group_letter(index), canonical::render_sum_of_products(&formula.variables, std::slice::from_ref(term)));
     }
+    println!();
+
+    return Ok(());
+}
+
+/**
+ * `deps <formula>`, lists for every column of the table the variables it depends on and those that
+ * are vacuous for it, flipping them never changes the column. Formulas with more than 16 variables
+ * are checked on the BDD of every column instead of the table
+**/
+fn command_deps(args: &str, settings: &Settings) -> CommandResult
+{
+    let formula = Formula::parse(args)?;
+    let size = formula.variables.len();
 
-    pub fn finished(&self) -> bool
+    let depends = if size <= MAX_TABULATED_VARIABLES
     {
-        return self.current >= self.last;
+        let table = table::evaluate_table(&formula.groups, size as u32, settings.ordering, settings.threads)?;
+        analysis::dependencies(&table)
     }
+    else
+    {
+        let mut bdd = bdd::Bdd::with_natural_order(size, bdd::DEFAULT_NODE_LIMIT);
+        let mut depends = Vec::<Vec<bool>>::new();
+
+        for node in execution::groups_to_nodes(&formula.groups)?
+        {
+            let root = bdd.from_node(&node)?;
+            depends.push(bdd.support(root));
+        }
 
-    pub fn advance(&mut self)
+        depends
+    };
+
+    for (repr, depends) in formula.reprs.iter().zip(depends)
     {
-        for col in 0..self.size
+        let names = |wanted: bool| {
+            (0..size).filter(|loc| depends[*loc] == wanted).map(|loc| formula.variables[loc].as_str()).collect::<Vec<&str>>().join(", ")
+        };
+
+        match (names(true), names(false))
         {
-            self.data[col as usize] = (self.current / pow2(self.size - col - 1)) % 2 == 0;
+            (essential, vacuous) if essential.is_empty() => println!("{}: constant, vacuous: {}", repr, vacuous),
+            (essential, vacuous) if vacuous.is_empty() => println!("{}: depends on {}", repr, essential),
+            (essential, vacuous) => println!("{}: depends on {}, vacuous: {}", repr, essential, vacuous),
         }
-        self.current += 1;
     }
+    println!();
+
+    return Ok(());
 }
 
-fn map_bool_cell(val: &bool) -> &'static str
+/// One line with the influence of every variable and the average and maximum sensitivity
+fn format_influence(variables: &[String], sensitivity: &analysis::Sensitivity) -> String
 {
-    if *val { "T" } else { "F" }
+    let influences = variables.iter().zip(sensitivity.influence.iter())
+        .map(|(var, influence)| format!("{} {:.3}", var, influence))
+        .collect::<Vec<String>>();
+
+    return format!(
+        "Influence: {}; average sensitivity {:.3}, max sensitivity {}",
+        influences.join(", "), sensitivity.average, sensitivity.max
+    );
 }
 
-fn process_input(stmt: String)
+/**
+ * `influence <formula>`, prints the table with the sensitivity of every row (how many variables
+ * flip the result when flipped) and, per variable, its influence (the fraction of rows where it
+ * does) and the Boolean derivative ∂f/∂x as a sum of products. The derivatives are minimized
+ * with `espresso`, exact minimization of one per variable is too slow for wide formulas
+**/
+fn command_influence(args: &str, settings: &Settings) -> CommandResult
 {
-    let tokens = scanner::tokenize(&stmt);
+    let formula = Formula::parse(args)?;
+    let size = formula.variables.len() as u32;
+
+    if size as usize > MAX_TABULATED_VARIABLES
+    {
+        return Err(format!("Influence is computed for at most {} variables", MAX_TABULATED_VARIABLES).into());
+    }
+
+    let table = table::evaluate_table(&formula.groups, size, settings.ordering, settings.threads)?;
+    let truth = table::truth_vector(&formula.groups, size, settings.threads)?;
+    let sensitivity = analysis::sensitivity(&truth, size);
+
+    let notes = (0..table.outputs.len() as u32)
+        .map(|position| sensitivity.rows[settings.ordering.minterm_at(size, position) as usize].to_string())
+        .collect::<Vec<String>>();
+    let notes = notes.iter().map(String::as_str).collect::<Vec<&str>>();
 
-    // empty query, only the EOF token is present
-    if tokens.len() == 1 { return; }
+    println!("{}", render_annotated_table(
+        &formula.variables, &formula.reprs, &table.inputs, &table.outputs, Some(("sensitivity", &notes))
+    ));
 
-    let compiled_result = compiler::compile(&tokens);
+    let mut builder = Builder::default().set_header(["variable", "influence", "∂f/∂x"]);
 
-    if let Some(error_token) = compiled_result.error_token
+    for (loc, var) in formula.variables.iter().enumerate()
     {
-        println!("Error at token: \"{}\"\n", error_token.lexeme);
-        return;
+        let derivative = analysis::derivative(&truth, size, loc);
+        let spec = espresso::from_truth_vectors(std::slice::from_ref(&derivative), &[]);
+        let products = espresso::output_products(&espresso::minimize(size, 1, spec), size, 0);
+        let rows = derivative.iter().filter(|changes| **changes).count();
+
+        builder = builder.add_row([
+            var.clone(),
+            format!("{:.3} ({}/{})", sensitivity.influence[loc], rows, truth.len()),
+            canonical::render_sum_of_products(&formula.variables, &products),
+        ]);
     }
 
-    let groups = execution::subexpression_groups(compiled_result.root.as_ref().unwrap());
-    let reprs = execution::groups_to_string(&groups, &compiled_result.variables);
+    println!("{}", builder.build().with(Style::modern()));
+    println!("Average sensitivity: {:.3}, max sensitivity: {}\n", sensitivity.average, sensitivity.max);
 
-    let mut iter = BoolPermutationsIterator::new(compiled_result.variables.len() as u32);
-    let mut row_results = vec![false; groups.len()];
+    return Ok(());
+}
 
-    let mut builder_input = Builder::default().set_header(&compiled_result.variables[..]);
-    let mut builder_output = Builder::default().set_header(&reprs[..]);
+/**
+ * `anf <formula>`, prints the algebraic normal form of every column of the table, an XOR of
+ * products of variables, along with its algebraic degree
+**/
+fn command_anf(args: &str, settings: &Settings) -> CommandResult
+{
+    let formula = Formula::parse(args)?;
+    let size = formula.variables.len() as u32;
 
-    loop {
-        iter.advance();
+    if size as usize > MAX_TABULATED_VARIABLES
+    {
+        return Err(format!("Algebraic normal forms are computed for at most {} variables", MAX_TABULATED_VARIABLES).into());
+    }
 
-        let table_row_input = iter.get()
-                .iter()
-                .map(map_bool_cell)
-                .collect::<Vec<&'static str>>();
+    let ordering = RowOrdering { order: RowOrder::Binary, reversed: false };
+    let table = table::evaluate_table(&formula.groups, size, ordering, settings.threads)?;
 
-        builder_input = builder_input.add_row(table_row_input);
+    for (col, repr) in formula.reprs.iter().enumerate()
+    {
+        let truth = table.outputs.iter().map(|row| row[col]).collect::<Vec<bool>>();
+        let coefficients = anf::mobius_transform(&truth);
+
+        println!("{}: {}", repr, anf::render(&coefficients, &formula.variables));
+        println!("Degree: {}", anf::degree(&coefficients));
+    }
+    println!();
+
+    return Ok(());
+}
 
-        execution::evaluate(&groups, iter.get(), &mut row_results[..]);
+/// Table with a row per function and a `T`/`F` column per class of Post's lattice
+fn render_post_classes(names: &[String], truths: &[(Vec<bool>, u32)]) -> String
+{
+    let mut header = vec![String::new()];
+    header.extend(post::POST_CLASSES.iter().map(|class| class.to_string()));
+    let mut builder = Builder::default().set_header(header);
+
+    for (name, (truth, size)) in names.iter().zip(truths.iter())
+    {
+        let mut cells = vec![name.clone()];
+        cells.extend(post::POST_CLASSES.iter().map(|class| String::from(map_bool_cell(&class.contains(truth, *size)))));
+        builder = builder.add_row(cells);
+    }
 
-        let table_row_output = row_results
-                        .iter()
-                        .map(map_bool_cell)
-                        .collect::<Vec<&'static str>>();
+    return builder.build().with(Style::modern()).to_string();
+}
+
+/**
+ * `post <formula>`, shows which classes of Post's lattice (0-preserving, 1-preserving, monotone,
+ * self-dual, affine) every column of the table belongs to
+**/
+fn command_post(args: &str, settings: &Settings) -> CommandResult
+{
+    let formula = Formula::parse(args)?;
+    let size = formula.variables.len() as u32;
 
-        builder_output = builder_output.add_row(table_row_output);
+    if size as usize > MAX_TABULATED_VARIABLES
+    {
+        return Err(format!("Post classes are computed for at most {} variables", MAX_TABULATED_VARIABLES).into());
+    }
+
+    let ordering = RowOrdering { order: RowOrder::Binary, reversed: false };
+    let table = table::evaluate_table(&formula.groups, size, ordering, settings.threads)?;
+
+    let truths = (0..formula.groups.len())
+        .map(|col| (table.outputs.iter().map(|row| row[col]).collect::<Vec<bool>>(), size))
+        .collect::<Vec<(Vec<bool>, u32)>>();
+
+    println!("{}", render_post_classes(&formula.reprs, &truths));
+    return Ok(());
+}
+
+/**
+ * `complete {&, !}`, checks whether a set of connectives is functionally complete with Post's
+ * criterion, naming for every class a connective outside it or explaining why none is
+**/
+fn command_complete(args: &str) -> CommandResult
+{
+    let connectives = post::parse_connectives(args)?;
 
-        if iter.finished() {
-            break;
+    let names = connectives.iter().map(|connective| String::from(connective.symbol())).collect::<Vec<String>>();
+    let truths = connectives.iter().map(|connective| (connective.truth(), connective.arity())).collect::<Vec<(Vec<bool>, u32)>>();
+    println!("{}", render_post_classes(&names, &truths));
+
+    let completeness = post::completeness(&connectives);
+    let missing = completeness.iter()
+        .filter(|(_, escape)| escape.is_none())
+        .map(|(class, _)| class.to_string())
+        .collect::<Vec<String>>();
+
+    if missing.is_empty()
+    {
+        let escapes = completeness.iter()
+            .map(|(class, escape)| format!("{} by {}", class, escape.unwrap().symbol()))
+            .collect::<Vec<String>>();
+
+        println!("Functionally complete, every class is escaped: {}\n", escapes.join(", "));
+    }
+    else
+    {
+        let (last, rest) = missing.split_last().unwrap();
+        let classes = if rest.is_empty() { last.clone() } else { format!("{} and {}", rest.join(", "), last) };
+
+        println!("Not functionally complete, every connective is {} and so is anything built from them\n", classes);
+    }
+
+    return Ok(());
+}
+
+/**
+ * `gates nand : <formula>`, `gates nor : <formula>` or `gates {&, ^, 1} : <formula>`, rewrites the
+ * formula into an equivalent expression that only uses the connectives of the basis and counts its
+ * gates, sharing identical subexpressions
+**/
+fn command_gates(args: &str) -> CommandResult
+{
+    let (basis, source) = args.split_once(':').ok_or("Usage: gates <nand | nor | {connectives}> : <formula>")?;
+
+    let basis = post::parse_connectives(basis)?;
+    let formula = Formula::parse(source)?;
+
+    let (circuit, root) = match gates::rewrite(&formula.root, &basis)
+    {
+        Ok(result) => result,
+        Err(err) if !post::is_complete(&basis) => {
+            return Err(format!("{}, which is not functionally complete (see complete)", err).into());
+        },
+        Err(err) => { return Err(err.into()); }
+    };
+
+    // Constants the basis builds from an input use a fresh one when the formula has no variables,
+    // any gate with operands reaches it
+    let mut variables = formula.variables.clone();
+    if variables.is_empty() && circuit.tree_size(root) > 1
+    {
+        variables.push(String::from("x"));
+        println!("The formula has no variables, the constant is built from a fresh input x");
+    }
+
+    if circuit.tree_size(root) <= gates::MAX_RENDERED_NODES
+    {
+        println!("{}", circuit.render(root, &variables));
+    }
+    else
+    {
+        println!("The expression has more than {} nodes when written out", gates::MAX_RENDERED_NODES);
+    }
+
+    let counts = circuit.gate_counts(root);
+    let total = counts.values().sum::<usize>();
+    let detail = counts.iter().map(|(symbol, count)| format!("{} {}", count, symbol)).collect::<Vec<String>>();
+
+    match detail.is_empty()
+    {
+        true => println!("0 gate(s)\n"),
+        false => println!("{} gate(s): {}\n", total, detail.join(", ")),
+    }
+
+    return Ok(());
+}
+
+/// Writes a formula with its constants as `TRUE` and `FALSE`, so that it can be entered again
+fn render_with_literals(node: &ASTNode, variables: &[String]) -> Result<String, execution::EvalError>
+{
+    let rendered = execution::node_to_string(node, variables)?;
+    return Ok(rendered.replace(execution::SYMBOL_TRUE, "TRUE").replace(execution::SYMBOL_FALSE, "FALSE"));
+}
+
+/**
+ * `simplify <formula>`, simplifies the formula step by step with the laws of Boolean algebra (see
+ * `simplify::LAWS`), printing every intermediate formula with the law that produced it. The last
+ * one is where no law applies any more, which is not always the simplest equivalent formula
+**/
+fn command_simplify(args: &str) -> CommandResult
+{
+    let formula = Formula::parse(args)?;
+    let steps = simplify::simplify(&formula.root, simplify::DEFAULT_STEP_LIMIT);
+
+    println!("   {}", render_with_literals(&formula.root, &formula.variables)?);
+
+    for (index, step) in steps.iter().enumerate()
+    {
+        println!("{}. {}    ({})", index + 1, render_with_literals(&step.result, &formula.variables)?, step.law);
+    }
+
+    let result = steps.last().map(|step| &step.result).unwrap_or(&formula.root);
+    let rendered = render_with_literals(result, &formula.variables)?;
+
+    match steps.len() == simplify::DEFAULT_STEP_LIMIT
+    {
+        true => println!("Stopped at the limit of {} steps: {}\n", steps.len(), rendered),
+        false => println!("No further law applies after {} step(s): {}\n", steps.len(), rendered),
+    }
+
+    return Ok(());
+}
+
+/// Locations of the variables named in `names`, separated by commas
+fn variable_locations(variables: &[String], names: &str) -> Result<Vec<usize>, FormulaError>
+{
+    return names.split(',')
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            variables.iter().position(|var| var == name).ok_or(FormulaError::UnknownVariable(String::from(name)))
+        })
+        .collect();
+}
+
+/**
+ * `bdd [order=p,q] [sift] [restrict=p=T,q=F] [dot | dot=<path>] : <formula>` or `bdd <formula>`,
+ * builds the reduced ordered BDD of the formula and reports its size, a model and the number of
+ * models. Variables listed in `order` are tested first, `sift` then looks for a smaller order and
+ * `restrict` fixes variables before anything is reported. With `dot` the BDD is printed, or
+ * written to a file, in Graphviz DOT format. Given `<formula> ; <formula>` it checks equivalence,
+ * only `restrict` applies then
+**/
+fn command_bdd(args: &str) -> CommandResult
+{
+    let (options, source) = args.split_once(':').unwrap_or(("", args));
+    let sources = source.split(';').collect::<Vec<&str>>();
+
+    if sources.len() > 2
+    {
+        return Err("Usage: bdd [options] : <formula> [; <formula>]".into());
+    }
+
+    let formulas = formula::parse_shared(&sources)?;
+    let variables = &formulas[0].variables;
+
+    let mut order = Vec::<usize>::new();
+    let mut sift = false;
+    let mut restrictions = Vec::<(usize, bool)>::new();
+    let mut dot: Option<Option<&str>> = None;
+
+    for option in options.split_whitespace()
+    {
+        match option.split_once('=')
+        {
+            Some(("order", names)) => {
+                order = variable_locations(variables, names)?;
+
+                if let Some(index) = (1..order.len()).find(|index| order[..*index].contains(&order[*index]))
+                {
+                    return Err(format!("Variable {} is listed more than once in order", variables[order[index]]).into());
+                }
+            },
+            Some(("restrict", assignment)) => {
+                for (name, value) in formula::parse_assignment(&assignment.replace(',', " "))?
+                {
+                    let loc = variable_locations(variables, &name)?[0];
+                    if restrictions.iter().any(|(restricted, _)| *restricted == loc)
+                    {
+                        return Err(format!("Variable {} is restricted more than once", name).into());
+                    }
+                    restrictions.push((loc, value));
+                }
+            },
+            Some(("dot", path)) => { dot = Some(Some(path)); },
+            None if option == "sift" => { sift = true; },
+            None if option == "dot" => { dot = Some(None); },
+            _ => {
+                return Err(format!("Unknown option \"{}\", expected order=p,q, sift, restrict=p=T,q=F or dot[=path]", option).into());
+            }
         }
     }
 
-    let style = Style::modern();    
+    if formulas.len() == 2 && (!order.is_empty() || sift || dot.is_some())
+    {
+        return Err("Only restrict applies when checking equivalence, order, sift and dot need a single formula".into());
+    }
 
-    let table_input = builder_input
-                    .build()
-                    .with(style.clone());
+    // Listed variables come first, the others follow in the order they appear
+    for loc in 0..variables.len()
+    {
+        if !order.contains(&loc) { order.push(loc); }
+    }
 
-    let table_output = builder_output
-                    .build()
-                    .with(Modify::new(tabled::Full).with(Wrap::new(20)))
-                    .with(style.clone());
+    let mut bdd = bdd::Bdd::new(order, bdd::DEFAULT_NODE_LIMIT);
+    let mut roots = Vec::<bdd::NodeId>::new();
+
+    for formula in &formulas
+    {
+        let mut root = bdd.from_node(&formula.root)?;
+        for (loc, value) in &restrictions
+        {
+            root = bdd.restrict(root, *loc, *value)?;
+        }
+        roots.push(root);
+    }
 
-    let display_table = table_input.with(Concat::horizontal(table_output));
+    // Restricted variables no longer occur in the BDD, so models get their fixed values back
+    let complete = |mut model: Vec<bool>| {
+        for (loc, value) in &restrictions
+        {
+            model[*loc] = *value;
+        }
+        return model;
+    };
+
+    if let [left, right] = roots[..]
+    {
+        // Both functions share the manager, so they are equal exactly when their roots are
+        let difference = bdd.apply(OperatorType::BI_CNDL, left, right)?;
+        let difference = bdd.not(difference)?;
 
-    println!("{}", display_table.to_string());
+        match bdd.find_model(difference)
+        {
+            Some(model) => println!("Not equivalent, they differ on: {}\n", format_assignment(variables, &complete(model))),
+            None => println!("Equivalent\n"),
+        }
+
+        return Ok(());
+    }
+
+    let mut root = roots[0];
+    if sift
+    {
+        let (sifted, sifted_root) = bdd.sift(root)?;
+        bdd = sifted;
+        root = sifted_root;
+    }
+
+    let names = bdd.order().iter().map(|loc| variables[*loc].clone()).collect::<Vec<String>>();
+    println!("Order: {}", names.join(", "));
+    println!("Nodes: {}", bdd.size(root));
+
+    match bdd.find_model(root)
+    {
+        Some(model) => println!("Satisfiable: {}", format_assignment(variables, &complete(model))),
+        None => println!("Unsatisfiable"),
+    }
+
+    // The restricted function ignores the restricted variables, every model is counted once per value of each
+    let count = bdd.count_models(root) >> restrictions.len();
+    match restrictions.len()
+    {
+        0 => println!("{} of 2^{} assignment(s) satisfy the formula\n", count, variables.len()),
+        restricted => println!("{} of 2^{} assignment(s) of the unrestricted variables satisfy the formula\n", count, variables.len() - restricted),
+    }
+
+    match dot
+    {
+        Some(Some(path)) => {
+            fs::write(path, bdd.to_dot(root, variables))?;
+            println!("Wrote {} node(s) to {}\n", bdd.size(root), path);
+        },
+        Some(None) => println!("{}", bdd.to_dot(root, variables)),
+        None => (),
+    }
+
+    return Ok(());
+}
+
+/// `canonical <formula>`, prints the canonical sum of products and product of sums of the formula
+fn command_canonical(args: &str, settings: &Settings) -> CommandResult
+{
+    let formula = Formula::parse(args)?;
+    let size = formula.variables.len() as u32;
+
+    if size as usize > MAX_TABULATED_VARIABLES
+    {
+        return Err(format!("Canonical forms are computed for at most {} variables", MAX_TABULATED_VARIABLES).into());
+    }
+
+    let truth = table::truth_vector(&formula.groups, size, settings.threads)?;
+
+    println!("DNF: {}", canonical::canonical_dnf(&formula.variables, &truth));
+    println!("     {}", canonical::minterm_notation(&truth));
+    println!("CNF: {}", canonical::canonical_cnf(&formula.variables, &truth));
+    println!("     {}\n", canonical::maxterm_notation(&truth));
+    return Ok(());
+}
+
+/// `nnf <formula>`, pushes negations down to the variables
+fn command_nnf(args: &str) -> CommandResult
+{
+    let formula = Formula::parse(args)?;
+    let nnf = transform::to_nnf(&formula.root, transform::DEFAULT_SIZE_LIMIT)?;
+
+    println!("NNF: {}\n", execution::node_to_string(&nnf, &formula.variables)?);
+    return Ok(());
+}
+
+/// `cnf <formula>` and `dnf <formula>`, normal forms by rewriting instead of from the table
+fn command_normal_form(args: &str, conjunctive: bool) -> CommandResult
+{
+    let formula = Formula::parse(args)?;
+
+    if conjunctive
+    {
+        let clauses = transform::to_cnf(&formula.root, transform::DEFAULT_SIZE_LIMIT)?;
+        println!("CNF: {}\n", canonical::render_product_of_sums(&formula.variables, &clauses));
+    }
+    else
+    {
+        let products = transform::to_dnf(&formula.root, transform::DEFAULT_SIZE_LIMIT)?;
+        println!("DNF: {}\n", canonical::render_sum_of_products(&formula.variables, &products));
+    }
+
+    return Ok(());
+}
+
+/**
+ * `minimize <formula>`, prints a minimal sum of products found with Quine–McCluskey and Petrick's
+ * method, optionally followed by a don't-care clause. Functions with too many implicants to
+ * tabulate get a near-minimal one from `espresso` instead. The printed form is parsed and
+ * tabulated again to check it before it is shown
+**/
+fn command_minimize(args: &str, settings: &Settings) -> CommandResult
+{
+    let (source, dont_cares) = formula::split_dont_cares(args)?;
+    let formula = Formula::parse(source)?;
+    let size = formula.variables.len() as u32;
+
+    if formula.variables.len() > MAX_TABULATED_VARIABLES
+    {
+        return Err(format!("Minimization needs a table, at most {} variables are supported", MAX_TABULATED_VARIABLES).into());
+    }
+
+    let truth = table::truth_vector(&formula.groups, size, settings.threads)?;
+    let dont_care = match dont_cares
+    {
+        Some(dont_cares) => formula.dont_care_vector(&dont_cares, settings.threads)?,
+        None => vec![],
+    };
+
+    let (products, exact) = match minimize::minimal_sum_of_products(size, &truth, &dont_care, minimize::DEFAULT_COVER_LIMIT)
+    {
+        Err(MinimizeError::TooManyImplicants { .. }) => {
+            let dont_cares = if dont_care.is_empty() { vec![] } else { vec![dont_care.clone()] };
+            let spec = espresso::from_truth_vectors(std::slice::from_ref(&truth), &dont_cares);
+            (espresso::output_products(&espresso::minimize(size, 1, spec), size, 0), false)
+        },
+        result => (result?, true),
+    };
+
+    // Constants are rendered as literals that can be parsed again
+    let rendered = match products.iter().any(|product| product.is_empty()) || products.is_empty()
+    {
+        true => canonical::render_sum_of_products(&formula.variables, &products),
+        false => execution::node_to_string(&minimize::sum_of_products_node(&products), &formula.variables)?,
+    };
+
+    let check = Formula::parse_with_variables(&rendered, formula.variables.clone())?;
+    let agrees = table::truth_vector(&check.groups, size, settings.threads)?.iter().zip(truth.iter()).enumerate()
+        .all(|(minterm, (left, right))| left == right || dont_care.get(minterm) == Some(&true));
+
+    if !agrees
+    {
+        return Err(format!("Minimized form {} is not equivalent to the formula", rendered).into());
+    }
+
+    let literals = products.iter().map(|product| product.len()).sum::<usize>();
+
+    if exact
+    {
+        println!("Minimal SOP: {}", rendered);
+        println!("             {} product(s), {} literal(s)\n", products.len(), literals);
+    }
+    else
+    {
+        println!("Too many prime implicants for an exact result, found with espresso instead");
+        println!("Near-minimal SOP: {}", rendered);
+        println!("                  {} product(s), {} literal(s)\n", products.len(), literals);
+    }
+    return Ok(());
+}
+
+/**
+ * `espresso <formula>`, heuristic minimization of every result column together, optionally
+ * followed by a don't-care clause for the last column. The cubes come from the table for small
+ * formulas and from the DNF of each column otherwise, each column's sum of products is checked
+ * against it with the SAT solver before it is printed
+**/
+fn command_espresso(args: &str, settings: &Settings) -> CommandResult
+{
+    let (source, dont_cares) = formula::split_dont_cares(args)?;
+    let formula = Formula::parse(source)?;
+    let size = formula.variables.len() as u32;
+    let output_count = formula.groups.len();
+
+    if size > table::MAX_VARIABLES
+    {
+        return Err(format!("At most {} variables are supported", table::MAX_VARIABLES).into());
+    }
+
+    if output_count > espresso::MAX_OUTPUTS
+    {
+        return Err(format!("At most {} result columns are supported", espresso::MAX_OUTPUTS).into());
+    }
+
+    let nodes = execution::groups_to_nodes(&formula.groups)?;
+
+    // Don't-cares apply to the last column, the whole formula, and are needed as a syntax tree
+    // for the final check either way
+    let dont_care_node = match &dont_cares
+    {
+        Some(DontCares::Condition(source)) => Some(formula.parse_condition(source)?.root),
+        Some(DontCares::Minterms(minterms)) => {
+            if let Some(minterm) = minterms.iter().find(|minterm| (**minterm as u64) >> size != 0)
+            {
+                return Err(FormulaError::InvalidDontCare(minterm.to_string()).into());
+            }
+
+            let products = minterms.iter().map(|minterm| Implicant::minterm(*minterm).to_term(size)).collect::<Vec<_>>();
+            Some(minimize::sum_of_products_node(&products))
+        },
+        None => None,
+    };
+
+    let spec = if formula.variables.len() <= MAX_TABULATED_VARIABLES
+    {
+        let ordering = RowOrdering { order: RowOrder::Binary, reversed: false };
+        let table = table::evaluate_table(&formula.groups, size, ordering, settings.threads)?;
+        let truths = (0..output_count)
+            .map(|output| table.outputs.iter().map(|row| row[output]).collect())
+            .collect::<Vec<Vec<bool>>>();
+
+        let mut dont_care_vectors = vec![vec![]; output_count];
+        if let Some(dont_cares) = &dont_cares
+        {
+            dont_care_vectors[output_count - 1] = formula.dont_care_vector(dont_cares, settings.threads)?;
+        }
+
+        espresso::from_truth_vectors(&truths, &dont_care_vectors)
+    }
+    else
+    {
+        let mut dont_care_cubes = vec![vec![]; output_count];
+        if let Some(node) = &dont_care_node
+        {
+            dont_care_cubes[output_count - 1] = transform::to_dnf(node, transform::DEFAULT_SIZE_LIMIT)?.iter()
+                .map(|term| Implicant::from_term(size, term))
+                .collect();
+        }
+
+        espresso::from_nodes(size, &nodes, dont_care_cubes, transform::DEFAULT_SIZE_LIMIT)?
+    };
+
+    let cover = espresso::minimize(size, output_count, spec);
+
+    for (output, (repr, node)) in formula.reprs.iter().zip(nodes.iter()).enumerate()
+    {
+        let products = espresso::output_products(&cover, size, output);
+        let sop = minimize::sum_of_products_node(&products);
+
+        // Outside the don't-cares both have to agree, which is when they agree once OR-ed with them
+        let (expected, found) = match &dont_care_node
+        {
+            Some(dont_care) if output == output_count - 1 => (
+                ASTNode::binary(OperatorType::OR, dont_care.clone(), node.clone()),
+                ASTNode::binary(OperatorType::OR, dont_care.clone(), sop.clone()),
+            ),
+            _ => (node.clone(), sop.clone()),
+        };
+
+        if sat::find_node_difference(&expected, &found, formula.variables.len()).is_some()
+        {
+            return Err(format!("Minimized form of {} is not equivalent to it", repr).into());
+        }
+
+        // Constants are rendered as literals that can be parsed again
+        let rendered = match products.iter().any(|product| product.is_empty()) || products.is_empty()
+        {
+            true => canonical::render_sum_of_products(&formula.variables, &products),
+            false => execution::node_to_string(&sop, &formula.variables)?,
+        };
+
+        println!("{}: {}", repr, rendered);
+    }
+
+    let literals = cover.iter().map(|cube| cube.inputs.literal_count(size)).sum::<u32>();
+    println!("{} product(s), {} literal(s)\n", cover.len(), literals);
+    return Ok(());
+}
+
+/**
+ * `dimacs <formula>` or `dimacs <path> : <formula>`, prints the Tseitin encoding of the formula
+ * in DIMACS CNF format, or writes it to a file
+**/
+fn command_dimacs(args: &str) -> CommandResult
+{
+    let (path, source) = match args.split_once(':')
+    {
+        Some((path, source)) => (Some(path.trim()), source),
+        None => (None, args),
+    };
+
+    let formula = Formula::parse(source)?;
+    let cnf = cnf::tseitin(&formula.root, formula.variables.len());
+    let dimacs = cnf::to_dimacs(&cnf, &formula.variables);
+
+    match path
+    {
+        Some(path) => {
+            fs::write(path, dimacs)?;
+            println!("Wrote {} variable(s) and {} clause(s) to {}\n", cnf.num_vars, cnf.clauses.len(), path);
+        },
+        None => println!("{}", dimacs),
+    }
+
+    return Ok(());
+}
+
+/**
+ * `equiv <formula> ; <formula>`, checks that both formulas have the same value in every row.
+ * Small formulas list every distinguishing assignment, larger ones report a single one
+**/
+fn command_equiv(args: &str, settings: &Settings) -> CommandResult
+{
+    let (left, right) = args.split_once(';').ok_or("Usage: equiv <formula> ; <formula>")?;
+
+    let formulas = formula::parse_shared(&[left, right])?;
+    let (left, right) = (&formulas[0], &formulas[1]);
+
+    if left.variables.len() > MAX_TABULATED_VARIABLES
+    {
+        match sat::find_difference(left, right)
+        {
+            Some(model) => println!("Not equivalent, they differ on: {}\n", format_assignment(&left.variables, &model)),
+            None => println!("Equivalent\n"),
+        }
+
+        return Ok(());
+    }
+
+    let differences = analysis::distinguishing_assignments(left, right, settings.ordering, settings.threads)?;
+
+    if differences.is_empty()
+    {
+        println!("Equivalent\n");
+        return Ok(());
+    }
+
+    let reprs = [left.reprs[left.reprs.len() - 1].clone(), right.reprs[right.reprs.len() - 1].clone()];
+    let inputs = differences.iter().map(|diff| diff.assignment.clone()).collect::<Vec<Vec<bool>>>();
+    let outputs = differences.iter().map(|diff| vec![diff.left, !diff.left]).collect::<Vec<Vec<bool>>>();
+
+    println!("Not equivalent, {} distinguishing assignment(s):", differences.len());
+    println!("{}", render_table(&left.variables, &reprs, &inputs, &outputs));
+    return Ok(());
+}
+
+/// `<premise>, <premise>, ... |- <conclusion>`, checks whether the conclusion follows from the premises
+fn command_argument(stmt: &str, settings: &Settings) -> CommandResult
+{
+    let (premises, conclusion) = stmt.split_once("|-").ok_or("Usage: <premise>, <premise>, ... |- <conclusion>")?;
+
+    let mut sources = premises.split(',').filter(|premise| !premise.trim().is_empty()).collect::<Vec<&str>>();
+    sources.push(conclusion);
+
+    let mut formulas = formula::parse_shared(&sources)?;
+    let conclusion = formulas.pop().unwrap();
+
+    if conclusion.variables.len() > MAX_TABULATED_VARIABLES
+    {
+        // The argument is valid when the premises imply the conclusion, a counterexample to
+        // that implication is one to the argument
+        let (conclusion_source, premise_sources) = sources.split_last().unwrap();
+        let premises = premise_sources.iter().map(|source| format!("({})", source)).collect::<Vec<String>>();
+        let implication = match premises.is_empty()
+        {
+            true => String::from(*conclusion_source),
+            false => format!("({}) => ({})", premises.join(" & "), conclusion_source),
+        };
+
+        let implication = Formula::parse(&implication)?;
+
+        match sat::find_counterexample(&implication)
+        {
+            Some(model) => println!("Invalid, counterexample: {}\n", format_assignment(&implication.variables, &model)),
+            None => println!("Valid, the conclusion holds whenever every premise does\n"),
+        }
+
+        return Ok(());
+    }
+
+    let argument = analysis::check_argument(&formulas, &conclusion, settings.ordering, settings.threads)?;
+
+    let reprs = formulas.iter()
+        .chain([&conclusion])
+        .flat_map(|formula| formula.reprs.iter().cloned())
+        .collect::<Vec<String>>();
+
+    let outputs = (0..argument.rows.len())
+        .map(|row| argument.tables.iter().flat_map(|table| table.outputs[row].iter().copied()).collect())
+        .collect::<Vec<Vec<bool>>>();
+
+    let notes = argument.rows.iter()
+        .map(|kind| match kind
+        {
+            RowKind::NotCritical => "",
+            RowKind::Critical => "critical",
+            RowKind::Counterexample => "COUNTEREXAMPLE",
+        })
+        .collect::<Vec<&str>>();
+
+    println!("{}", render_annotated_table(
+        &conclusion.variables, &reprs, &argument.tables[0].inputs, &outputs, Some(("", &notes))
+    ));
+
+    let critical = argument.rows.iter().filter(|kind| **kind != RowKind::NotCritical).count();
+    let counterexamples = argument.rows.iter().filter(|kind| **kind == RowKind::Counterexample).count();
+
+    if argument.is_valid()
+    {
+        println!("Valid, the conclusion holds in all {} critical row(s)\n", critical);
+    }
+    else
+    {
+        println!("Invalid, {} of {} critical row(s) are counterexamples\n", counterexamples, critical);
+    }
+
+    return Ok(());
+}
+
+/**
+ * Splits `stmt` into a command keyword and its arguments. Lines that do not start with a
+ * command keyword are formulas to tabulate, and so are lines that parse as a formula as a
+ * whole: `sat & p` is the table of a variable named `sat`. No command line is also a formula,
+ * since a formula cannot have a variable followed by anything but an operator
+**/
+fn split_command(stmt: &str) -> (Option<&str>, &str)
+{
+    const COMMANDS: [&str; 22] = [
+        "eval", "equiv", "sat", "valid", "count", "models", "canonical", "nnf", "cnf", "dnf", "dimacs", "minimize",
+        "espresso", "kmap", "bdd", "deps", "influence", "anf", "post", "complete",
+        "gates", "simplify"
+    ];
+
+    let stmt = stmt.trim_start();
+    let keyword_end = stmt.find(|ch: char| !ch.is_ascii_alphanumeric()).unwrap_or(stmt.len());
+    let (keyword, args) = stmt.split_at(keyword_end);
+
+    if COMMANDS.contains(&keyword) && (args.is_empty() || args.starts_with(char::is_whitespace))
+    {
+        let is_formula = formula::split_dont_cares(stmt).is_ok_and(|(source, _)| Formula::parse(source).is_ok());
+        if !is_formula { return (Some(keyword), args); }
+    }
+
+    return (None, stmt);
+}
+
+/**
+ * Replaces every `@path` in `stmt` with the formula read from the DIMACS CNF file at `path`,
+ * wrapped in parentheses. The path runs up to the next whitespace
+**/
+fn expand_file_references(stmt: &str) -> Result<String, Box<dyn Error>>
+{
+    let mut expanded = String::with_capacity(stmt.len());
+    let mut rest = stmt;
+
+    while let Some(start) = rest.find('@')
+    {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let path = &rest[..end];
+        rest = &rest[end..];
+
+        let text = fs::read_to_string(path).map_err(|err| format!("Cannot read \"{}\": {}", path, err))?;
+        let (cnf, names) = cnf::parse_dimacs(&text)?;
+
+        expanded.push('(');
+        expanded.push_str(&cnf::cnf_to_source(&cnf, &names));
+        expanded.push(')');
+    }
+
+    expanded.push_str(rest);
+    return Ok(expanded);
+}
+
+fn process_input(stmt: String, settings: &Settings)
+{
+    let stmt = match expand_file_references(&stmt)
+    {
+        Ok(stmt) => stmt,
+        Err(err) => {
+            println!("{}\n", err);
+            return;
+        }
+    };
+
+    let result = match split_command(&stmt)
+    {
+        (Some("eval"), args) => command_eval(args),
+        (Some("equiv"), args) => command_equiv(args, settings),
+        (Some("sat"), args) => command_sat(args),
+        (Some("valid"), args) => command_valid(args),
+        (Some("count"), args) => command_count(args, settings),
+        (Some("models"), args) => command_models(args),
+        (Some("canonical"), args) => command_canonical(args, settings),
+        (Some("nnf"), args) => command_nnf(args),
+        (Some("cnf"), args) => command_normal_form(args, true),
+        (Some("dnf"), args) => command_normal_form(args, false),
+        (Some("dimacs"), args) => command_dimacs(args),
+        (Some("minimize"), args) => command_minimize(args, settings),
+        (Some("espresso"), args) => command_espresso(args, settings),
+        (Some("kmap"), args) => command_kmap(args, settings),
+        (Some("bdd"), args) => command_bdd(args),
+        (Some("deps"), args) => command_deps(args, settings),
+        (Some("influence"), args) => command_influence(args, settings),
+        (Some("anf"), args) => command_anf(args, settings),
+        (Some("post"), args) => command_post(args, settings),
+        (Some("complete"), args) => command_complete(args),
+        (Some("gates"), args) => command_gates(args),
+        (Some("simplify"), args) => command_simplify(args),
+        (None, stmt) if stmt.contains("|-") => command_argument(stmt, settings),
+        (_, source) => command_table(source, settings),
+    };
+
+    match result
+    {
+        Ok(()) => (),
+        Err(err) => match err.downcast_ref::<FormulaError>()
+        {
+            Some(FormulaError::Empty) if split_command(&stmt).0.is_none() => (),
+            _ => { println!("{}\n", err); }
+        }
+    }
 }
 
 
 
 fn main()
 {
+    let settings = match Settings::from_args()
+    {
+        Ok(settings) => settings,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(2);
+        }
+    };
+
     println!("Welcome to ttbl!");
     println!("Press <Ctrl-D> to exit\n");
 
@@ -145,7 +1433,7 @@ fn main()
         match readline {
             Ok(line) => {
                 rl.add_history_entry(line.as_str());
-                process_input(line);
+                process_input(line, &settings);
             },
             Err(ReadlineError::Interrupted) => {
                 continue;
@@ -0,0 +1,286 @@
+use crate::canonical::{self, Term};
+use crate::compiler::ASTNode;
+use crate::scanner::OperatorType;
+use std::collections::{BTreeSet, HashSet};
+
+/// Default number of partial covers Petrick's method may keep before giving up
+pub const DEFAULT_COVER_LIMIT: usize = 10_000;
+
+/**
+ * Number of implicants the tabulation of `minimal_sum_of_products` may build before giving up.
+ * Dense functions reach up to 3^n of them, which for 16 variables takes minutes
+**/
+pub const DEFAULT_IMPLICANT_LIMIT: usize = 500_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MinimizeError
+{
+    /// Petrick's method needed more than the given number of partial covers
+    TooLarge { limit: usize },
+    /// The tabulation of prime implicants built more than the given number of implicants
+    TooManyImplicants { limit: usize },
+}
+
+impl std::fmt::Display for MinimizeError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self
+        {
+            MinimizeError::TooLarge { limit } => write!(f, "Minimization exceeds the limit of {} partial covers", limit),
+            MinimizeError::TooManyImplicants { limit } => write!(f, "Minimization exceeds the limit of {} implicants", limit),
+        }
+    }
+}
+
+impl std::error::Error for MinimizeError {}
+
+/**
+ * A product of literals as a cube over minterm indices (see `RowOrdering::minterm_at`): bits set
+ * in `mask` are free, the remaining bits of a covered minterm equal those of `value`
+**/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Implicant
+{
+    pub value: u32,
+    pub mask: u32,
+}
+
+impl Implicant
+{
+    pub fn minterm(minterm: u32) -> Self
+    {
+        Self { value: minterm, mask: 0 }
+    }
+
+    pub fn covers(&self, minterm: u32) -> bool
+    {
+        return minterm & !self.mask == self.value;
+    }
+
+    /// Whether every minterm of `other` is also one of `self`
+    pub fn contains(&self, other: &Implicant) -> bool
+    {
+        return self.mask & other.mask == other.mask && (self.value ^ other.value) & !self.mask == 0;
+    }
+
+    /// Whether the two cubes share a minterm
+    pub fn intersects(&self, other: &Implicant) -> bool
+    {
+        return (self.value ^ other.value) & !self.mask & !other.mask == 0;
+    }
+
+    pub fn literal_count(&self, size: u32) -> u32
+    {
+        return size - self.mask.count_ones();
+    }
+
+    /// The cube of a product of literals (see `canonical::Term`) over `size` variables
+    pub fn from_term(size: u32, term: &Term) -> Self
+    {
+        let mut implicant = Implicant { value: 0, mask: (1 << size) - 1 };
+
+        for (loc, positive) in term
+        {
+            let bit = 1 << (size - *loc as u32 - 1);
+            implicant.mask &= !bit;
+            if *positive { implicant.value |= bit; }
+        }
+
+        return implicant;
+    }
+
+    /// The literals of the product, first variable first
+    pub fn to_term(&self, size: u32) -> Term
+    {
+        return (0..size)
+            .map(|col| (col, 1 << (size - col - 1)))
+            .filter(|(_, bit)| self.mask & bit == 0)
+            .map(|(col, bit)| (col as usize, self.value & bit != 0))
+            .collect();
+    }
+}
+
+/**
+ * Prime implicants of the function of `size` variables that is true on `minterms`. Follows the
+ * Quine–McCluskey tabulation: implicants differing in a single fixed bit are merged into one with
+ * that bit free, level by level, and those that never merge are prime. Fails once the levels hold
+ * more than `limit` implicants between them
+**/
+pub fn prime_implicants(size: u32, minterms: &[u32], limit: usize) -> Result<Vec<Implicant>, MinimizeError>
+{
+    let mut primes = Vec::<Implicant>::new();
+    let mut level: HashSet<Implicant> = minterms.iter().map(|minterm| Implicant::minterm(*minterm)).collect();
+    let mut built = 0usize;
+
+    while !level.is_empty()
+    {
+        built += level.len();
+        if built > limit
+        {
+            return Err(MinimizeError::TooManyImplicants { limit });
+        }
+
+        let mut next = HashSet::<Implicant>::new();
+        let mut merged = HashSet::<Implicant>::new();
+
+        for implicant in &level
+        {
+            for bit in (0..size).map(|col| 1 << col)
+            {
+                // Every pair is found once, from the side where the bit is clear
+                if (implicant.mask | implicant.value) & bit != 0 { continue; }
+
+                let partner = Implicant { value: implicant.value | bit, mask: implicant.mask };
+                if level.contains(&partner)
+                {
+                    next.insert(Implicant { value: implicant.value, mask: implicant.mask | bit });
+                    merged.insert(*implicant);
+                    merged.insert(partner);
+                }
+            }
+        }
+
+        primes.extend(level.iter().filter(|implicant| !merged.contains(implicant)));
+        level = next;
+    }
+
+    primes.sort_unstable();
+    return Ok(primes);
+}
+
+/// Removes every set that contains another set of the list
+fn remove_supersets(sets: Vec<BTreeSet<usize>>) -> Vec<BTreeSet<usize>>
+{
+    let mut sets = sets;
+    sets.sort_by_key(|set| set.len());
+    sets.dedup();
+
+    let mut kept: Vec<BTreeSet<usize>> = vec![];
+    for set in sets
+    {
+        if !kept.iter().any(|smaller| smaller.is_subset(&set))
+        {
+            kept.push(set);
+        }
+    }
+
+    return kept;
+}
+
+/**
+ * Chooses the fewest `primes` that cover every minterm, preferring fewer literals between covers
+ * of the same size. Essential primes, the only ones covering some minterm, are taken first and
+ * Petrick's method picks among the rest. Fails if it needs more than `limit` partial covers
+**/
+pub fn minimum_cover(size: u32, primes: &[Implicant], minterms: &[u32], limit: usize) -> Result<Vec<Implicant>, MinimizeError>
+{
+    let covering = |minterm: u32| (0..primes.len()).filter(move |index| primes[*index].covers(minterm));
+
+    let mut chosen = BTreeSet::<usize>::new();
+    for minterm in minterms
+    {
+        let mut candidates = covering(*minterm);
+        if let (Some(only), None) = (candidates.next(), candidates.next())
+        {
+            chosen.insert(only);
+        }
+    }
+
+    // Petrick's method: multiply out the product of sums "one of these primes is chosen" for
+    // every minterm left, absorbing covers that contain other covers as it goes
+    let mut covers = vec![BTreeSet::<usize>::new()];
+
+    for minterm in minterms
+    {
+        if chosen.iter().any(|index| primes[*index].covers(*minterm)) { continue; }
+
+        let candidates = covering(*minterm).collect::<Vec<usize>>();
+        let mut next = Vec::<BTreeSet<usize>>::with_capacity(covers.len() * candidates.len());
+
+        for cover in &covers
+        {
+            if candidates.iter().any(|index| cover.contains(index))
+            {
+                next.push(cover.clone());
+                continue;
+            }
+
+            for index in &candidates
+            {
+                let mut extended = cover.clone();
+                extended.insert(*index);
+                next.push(extended);
+            }
+        }
+
+        covers = remove_supersets(next);
+
+        if covers.len() > limit
+        {
+            return Err(MinimizeError::TooLarge { limit });
+        }
+    }
+
+    let literals = |cover: &BTreeSet<usize>| cover.iter().map(|index| primes[*index].literal_count(size)).sum::<u32>();
+    let best = covers.into_iter()
+        .min_by_key(|cover| (cover.len(), literals(cover)))
+        .unwrap_or_default();
+
+    let mut cover = chosen.union(&best).map(|index| primes[*index]).collect::<Vec<Implicant>>();
+
+    // Larger cubes first, then in the order of their variables
+    cover.sort_by_key(|implicant| (implicant.literal_count(size), implicant.to_term(size)));
+    return Ok(cover);
+}
+
+/**
+ * Minimal sum of products of a truth vector (see `table::truth_vector`) of `size` variables, as a
+ * list of products in the form of `canonical::render_sum_of_products`. Rows set in `dont_care`
+ * (indexed the same way, or empty) may be covered or not, whichever gives the smaller result.
+ * Fails on functions with more than `DEFAULT_IMPLICANT_LIMIT` implicants, see `espresso` for those
+**/
+pub fn minimal_sum_of_products(size: u32, truth: &[bool], dont_care: &[bool], limit: usize) -> Result<Vec<Term>, MinimizeError>
+{
+    let free = |minterm: &u32| dont_care.get(*minterm as usize) == Some(&true);
+
+    let minterms = canonical::minterms(truth).into_iter().filter(|minterm| !free(minterm)).collect::<Vec<u32>>();
+    let mut allowed = minterms.clone();
+    allowed.extend((0..dont_care.len() as u32).filter(free));
+
+    // Constant functions need no tabulation, which is at its largest for them
+    if minterms.is_empty()
+    {
+        return Ok(vec![]);
+    }
+
+    if allowed.len() == 1 << size
+    {
+        return Ok(vec![vec![]]);
+    }
+
+    let primes = prime_implicants(size, &allowed, DEFAULT_IMPLICANT_LIMIT)?;
+    let cover = minimum_cover(size, &primes, &minterms, limit)?;
+
+    return Ok(cover.iter().map(|implicant| implicant.to_term(size)).collect());
+}
+
+/// Builds the syntax tree of a sum of products, `FALSE` without products and `TRUE` for an empty product
+pub fn sum_of_products_node(products: &[Term]) -> Box<ASTNode>
+{
+    let product_node = |product: &Term| {
+        product.iter()
+            .map(|(loc, positive)| match positive
+            {
+                true => ASTNode::variable(*loc),
+                false => ASTNode::unary(OperatorType::NOT, ASTNode::variable(*loc)),
+            })
+            .reduce(|left, right| ASTNode::binary(OperatorType::AND, left, right))
+            .unwrap_or_else(|| ASTNode::literal(true))
+    };
+
+    return products.iter()
+        .map(product_node)
+        .reduce(|left, right| ASTNode::binary(OperatorType::OR, left, right))
+        .unwrap_or_else(|| ASTNode::literal(false));
+}
@@ -0,0 +1,231 @@
+use crate::anf;
+
+/// The five maximal clones of Post's lattice, a set of connectives is complete when it escapes each one
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PostClass
+{
+    /// False when every argument is false
+    PreservesFalse,
+    /// True when every argument is true
+    PreservesTrue,
+    /// Never goes from true to false when an argument goes from false to true
+    Monotone,
+    /// Negating every argument negates the result
+    SelfDual,
+    /// An XOR of single variables and possibly the constant 1
+    Affine,
+}
+
+pub const POST_CLASSES: [PostClass; 5] = [
+    PostClass::PreservesFalse,
+    PostClass::PreservesTrue,
+    PostClass::Monotone,
+    PostClass::SelfDual,
+    PostClass::Affine,
+];
+
+impl std::fmt::Display for PostClass
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self
+        {
+            PostClass::PreservesFalse => write!(f, "0-preserving"),
+            PostClass::PreservesTrue => write!(f, "1-preserving"),
+            PostClass::Monotone => write!(f, "monotone"),
+            PostClass::SelfDual => write!(f, "self-dual"),
+            PostClass::Affine => write!(f, "affine"),
+        }
+    }
+}
+
+impl PostClass
+{
+    /// Whether the function `truth` (see `table::truth_vector`) of `size` variables belongs to the class
+    pub fn contains(&self, truth: &[bool], size: u32) -> bool
+    {
+        let all = truth.len() - 1;
+
+        return match self
+        {
+            PostClass::PreservesFalse => !truth[0],
+            PostClass::PreservesTrue => truth[all],
+            PostClass::Monotone => (0..truth.len()).all(|minterm| {
+                (0..size).map(|bit| 1 << bit).all(|bit| minterm & bit != 0 || truth[minterm] <= truth[minterm | bit])
+            }),
+            PostClass::SelfDual => (0..truth.len()).all(|minterm| truth[minterm] != truth[all & !minterm]),
+            PostClass::Affine => anf::degree(&anf::mobius_transform(truth)) <= 1,
+        };
+    }
+}
+
+/// Classes of Post's lattice the function `truth` of `size` variables belongs to, in the order of `POST_CLASSES`
+pub fn classes(truth: &[bool], size: u32) -> Vec<PostClass>
+{
+    return POST_CLASSES.iter().copied().filter(|class| class.contains(truth, size)).collect();
+}
+
+/// A connective that can be part of a basis, constants are connectives without arguments
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Connective
+{
+    False,
+    True,
+    Not,
+    And,
+    Or,
+    Implies,
+    Iff,
+    Xor,
+    Nand,
+    Nor,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConnectiveError
+{
+    /// The text is not the symbol or name of any connective
+    Unknown(String),
+    /// A set of connectives without any
+    Empty,
+    /// A connective applied to a different number of arguments than its arity
+    Arity { connective: Connective, given: usize },
+}
+
+impl std::fmt::Display for ConnectiveError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self
+        {
+            ConnectiveError::Unknown(name) => write!(
+                f, "Unknown connective \"{}\", expected one of 0, 1, !, &, |, =>, <=>, ^, nand, nor", name
+            ),
+            ConnectiveError::Empty => write!(f, "No connectives given"),
+            ConnectiveError::Arity { connective, given } => write!(
+                f, "{} takes {} argument(s), {} given", connective.symbol(), connective.arity(), given
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConnectiveError {}
+
+impl Connective
+{
+    /// Accepts the symbols of formulas, the names of the connectives and `0`/`1` for the constants
+    pub fn parse(name: &str) -> Result<Connective, ConnectiveError>
+    {
+        return match name.to_lowercase().as_str()
+        {
+            "0" | "f" | "false" => Ok(Connective::False),
+            "1" | "t" | "true" => Ok(Connective::True),
+            "!" | "~" | "not" => Ok(Connective::Not),
+            "&" | "and" => Ok(Connective::And),
+            "|" | "or" => Ok(Connective::Or),
+            "=>" | "implies" => Ok(Connective::Implies),
+            "<=>" | "iff" => Ok(Connective::Iff),
+            "^" | "xor" => Ok(Connective::Xor),
+            "nand" => Ok(Connective::Nand),
+            "nor" => Ok(Connective::Nor),
+            _ => Err(ConnectiveError::Unknown(String::from(name))),
+        };
+    }
+
+    pub fn symbol(&self) -> &'static str
+    {
+        return match self
+        {
+            Connective::False => "0",
+            Connective::True => "1",
+            Connective::Not => "!",
+            Connective::And => "&",
+            Connective::Or => "|",
+            Connective::Implies => "=>",
+            Connective::Iff => "<=>",
+            Connective::Xor => "^",
+            Connective::Nand => "nand",
+            Connective::Nor => "nor",
+        };
+    }
+
+    pub fn arity(&self) -> u32
+    {
+        return match self
+        {
+            Connective::False | Connective::True => 0,
+            Connective::Not => 1,
+            _ => 2,
+        };
+    }
+
+    /// Value for the given arguments, which must be as many as the arity
+    pub fn apply(&self, args: &[bool]) -> Result<bool, ConnectiveError>
+    {
+        return match (self, args)
+        {
+            (Connective::False, []) => Ok(false),
+            (Connective::True, []) => Ok(true),
+            (Connective::Not, [a]) => Ok(!a),
+            (Connective::And, [a, b]) => Ok(*a && *b),
+            (Connective::Or, [a, b]) => Ok(*a || *b),
+            (Connective::Implies, [a, b]) => Ok(!a || *b),
+            (Connective::Iff, [a, b]) => Ok(a == b),
+            (Connective::Xor, [a, b]) => Ok(a != b),
+            (Connective::Nand, [a, b]) => Ok(!(*a && *b)),
+            (Connective::Nor, [a, b]) => Ok(!(*a || *b)),
+            _ => Err(ConnectiveError::Arity { connective: *self, given: args.len() }),
+        };
+    }
+
+    /// Truth vector over the arguments, the first one as the most significant bit
+    pub fn truth(&self) -> Vec<bool>
+    {
+        let arity = self.arity();
+
+        return (0..1u32 << arity)
+            .map(|minterm| (0..arity).rev().map(|bit| minterm >> bit & 1 == 1).collect::<Vec<bool>>())
+            .map(|args| self.apply(&args).expect("Arguments are as many as the arity"))
+            .collect();
+    }
+}
+
+/// Parses a set of connectives such as `{nand}` or `{&, ^, 1}`, the braces are optional
+pub fn parse_connectives(source: &str) -> Result<Vec<Connective>, ConnectiveError>
+{
+    let source = source.trim();
+    let source = source.strip_prefix('{').and_then(|inner| inner.strip_suffix('}')).unwrap_or(source);
+
+    let mut connectives = Vec::<Connective>::new();
+
+    for name in source.split(',').map(str::trim).filter(|name| !name.is_empty())
+    {
+        let connective = Connective::parse(name)?;
+        if !connectives.contains(&connective) { connectives.push(connective); }
+    }
+
+    if connectives.is_empty() { return Err(ConnectiveError::Empty); }
+    return Ok(connectives);
+}
+
+/**
+ * Post's criterion: a set of connectives is functionally complete when, for every one of the five
+ * classes, some connective lies outside it. Returns, per class in the order of `POST_CLASSES`, the
+ * first connective that escapes it or `None` when every connective belongs to it. Any `None`
+ * makes the set incomplete, since every function built from it stays in that class
+**/
+pub fn completeness(connectives: &[Connective]) -> Vec<(PostClass, Option<Connective>)>
+{
+    return POST_CLASSES.iter()
+        .map(|class| {
+            let escape = connectives.iter().find(|connective| !class.contains(&connective.truth(), connective.arity()));
+            (*class, escape.copied())
+        })
+        .collect();
+}
+
+/// Whether every class of `completeness` is escaped
+pub fn is_complete(connectives: &[Connective]) -> bool
+{
+    return completeness(connectives).iter().all(|(_, escape)| escape.is_some());
+}
@@ -0,0 +1,491 @@
+use crate::cnf::{self, Cnf, Lit, TseitinEncoder};
+use crate::compiler::ASTNode;
+use crate::formula::Formula;
+
+/// Literals inside the solver: variable `v` (counting from 0) is `2v`, its negation `2v + 1`
+type SolverLit = usize;
+
+fn to_solver_lit(lit: Lit) -> SolverLit
+{
+    let var = (lit.unsigned_abs() - 1) as usize;
+    return 2 * var + (lit < 0) as usize;
+}
+
+fn var_of(lit: SolverLit) -> usize
+{
+    return lit >> 1;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SatResult
+{
+    /// A model, `model[v]` is the value of CNF variable `v + 1`
+    Sat(Vec<bool>),
+    Unsat,
+}
+
+const ACTIVITY_DECAY: f64 = 0.95;
+const RESTART_UNIT: u64 = 100;
+
+/**
+ * Conflict driven clause learning solver: two watched literals for propagation, first UIP
+ * clause learning with non-chronological backjumping, activity based branching with phase
+ * saving and Luby restarts
+**/
+pub struct Solver
+{
+    num_vars: usize,
+    /// Original and learnt clauses. The first two literals of every clause are the watched ones,
+    /// and the first literal of a reason clause is the literal it implied
+    clauses: Vec<Vec<SolverLit>>,
+    /// Clauses watching each literal, visited when the literal becomes false
+    watches: Vec<Vec<usize>>,
+
+    assigns: Vec<Option<bool>>,
+    level: Vec<usize>,
+    reason: Vec<Option<usize>>,
+    trail: Vec<SolverLit>,
+    /// Position in `trail` where each decision level starts
+    trail_lim: Vec<usize>,
+    /// Next position of `trail` to propagate
+    qhead: usize,
+
+    activity: Vec<f64>,
+    var_inc: f64,
+    /// Last value of each variable, reused when branching on it again
+    phase: Vec<bool>,
+    seen: Vec<bool>,
+
+    /// Set once an empty clause has been derived
+    unsat: bool,
+}
+
+impl Solver
+{
+    pub fn new(num_vars: usize) -> Self
+    {
+        Self {
+            num_vars,
+            clauses: vec![],
+            watches: vec![vec![]; 2 * num_vars],
+            assigns: vec![None; num_vars],
+            level: vec![0; num_vars],
+            reason: vec![None; num_vars],
+            trail: vec![],
+            trail_lim: vec![],
+            qhead: 0,
+            activity: vec![0.0; num_vars],
+            var_inc: 1.0,
+            phase: vec![false; num_vars],
+            seen: vec![false; num_vars],
+            unsat: false,
+        }
+    }
+
+    pub fn from_cnf(cnf: &Cnf) -> Self
+    {
+        let mut solver = Self::new(cnf.num_vars);
+        for clause in &cnf.clauses
+        {
+            solver.add_clause(clause);
+        }
+        return solver;
+    }
+
+    fn value(&self, lit: SolverLit) -> Option<bool>
+    {
+        return self.assigns[var_of(lit)].map(|val| val != (lit & 1 == 1));
+    }
+
+    fn decision_level(&self) -> usize
+    {
+        return self.trail_lim.len();
+    }
+
+    fn enqueue(&mut self, lit: SolverLit, reason: Option<usize>)
+    {
+        let var = var_of(lit);
+        self.assigns[var] = Some(lit & 1 == 0);
+        self.level[var] = self.decision_level();
+        self.reason[var] = reason;
+        self.trail.push(lit);
+    }
+
+    fn cancel_until(&mut self, level: usize)
+    {
+        if self.decision_level() <= level { return; }
+
+        let start = self.trail_lim[level];
+        for lit in self.trail.drain(start..)
+        {
+            let var = var_of(lit);
+            self.phase[var] = self.assigns[var].unwrap();
+            self.assigns[var] = None;
+            self.reason[var] = None;
+        }
+
+        self.trail_lim.truncate(level);
+        self.qhead = self.trail.len();
+    }
+
+    fn attach(&mut self, clause: Vec<SolverLit>) -> usize
+    {
+        let index = self.clauses.len();
+        self.watches[clause[0]].push(index);
+        self.watches[clause[1]].push(index);
+        self.clauses.push(clause);
+        return index;
+    }
+
+    /**
+     * Adds a clause of DIMACS literals. Clauses can be added between calls to `solve`, e.g. to
+     * block a model that was already found
+    **/
+    pub fn add_clause(&mut self, clause: &[Lit])
+    {
+        if self.unsat { return; }
+        self.cancel_until(0);
+
+        let mut lits = clause.iter().map(|lit| to_solver_lit(*lit)).collect::<Vec<SolverLit>>();
+        lits.sort_unstable();
+        lits.dedup();
+
+        // Tautologies are always satisfied, as are clauses with a literal already true at level 0
+        if lits.windows(2).any(|pair| pair[0] ^ 1 == pair[1]) { return; }
+        if lits.iter().any(|lit| self.value(*lit) == Some(true)) { return; }
+
+        lits.retain(|lit| self.value(*lit).is_none());
+
+        match lits.len()
+        {
+            0 => { self.unsat = true; },
+            1 => {
+                self.enqueue(lits[0], None);
+                if self.propagate().is_some() { self.unsat = true; }
+            },
+            _ => { self.attach(lits); }
+        }
+    }
+
+    /// Propagates every pending assignment, returns the index of a conflicting clause if any
+    fn propagate(&mut self) -> Option<usize>
+    {
+        while self.qhead < self.trail.len()
+        {
+            let false_lit = self.trail[self.qhead] ^ 1;
+            self.qhead += 1;
+
+            let watching = std::mem::take(&mut self.watches[false_lit]);
+            let mut kept = Vec::with_capacity(watching.len());
+
+            for (position, &index) in watching.iter().enumerate()
+            {
+                if self.clauses[index][0] == false_lit
+                {
+                    self.clauses[index].swap(0, 1);
+                }
+
+                let first = self.clauses[index][0];
+                if self.value(first) == Some(true)
+                {
+                    kept.push(index);
+                    continue;
+                }
+
+                // Look for a new literal to watch instead of the false one
+                let replacement = (2..self.clauses[index].len())
+                    .find(|k| self.value(self.clauses[index][*k]) != Some(false));
+
+                if let Some(k) = replacement
+                {
+                    self.clauses[index].swap(1, k);
+                    let watched = self.clauses[index][1];
+                    self.watches[watched].push(index);
+                    continue;
+                }
+
+                kept.push(index);
+
+                if self.value(first) == Some(false)
+                {
+                    kept.extend_from_slice(&watching[position + 1..]);
+                    self.watches[false_lit] = kept;
+                    return Some(index);
+                }
+
+                self.enqueue(first, Some(index));
+            }
+
+            self.watches[false_lit] = kept;
+        }
+
+        return None;
+    }
+
+    fn bump(&mut self, var: usize)
+    {
+        self.activity[var] += self.var_inc;
+
+        if self.activity[var] > 1e100
+        {
+            for activity in self.activity.iter_mut() { *activity *= 1e-100; }
+            self.var_inc *= 1e-100;
+        }
+    }
+
+    /**
+     * Derives the first UIP clause from a conflict. The asserting literal is put first and a
+     * literal of the backjump level second. Returns the clause and the level to backjump to
+    **/
+    fn analyze(&mut self, conflict: usize) -> (Vec<SolverLit>, usize)
+    {
+        let mut learnt: Vec<SolverLit> = vec![0];
+        let mut pending = 0;
+        let mut implied: Option<SolverLit> = None;
+        let mut index = self.trail.len();
+        let mut clause = conflict;
+
+        loop {
+            let skip = if implied.is_some() { 1 } else { 0 };
+
+            for position in skip..self.clauses[clause].len()
+            {
+                let lit = self.clauses[clause][position];
+                let var = var_of(lit);
+
+                if self.seen[var] || self.level[var] == 0 { continue; }
+
+                self.seen[var] = true;
+                self.bump(var);
+
+                if self.level[var] >= self.decision_level() { pending += 1; } else { learnt.push(lit); }
+            }
+
+            // Walk back to the next literal of the current level involved in the conflict
+            loop {
+                index -= 1;
+                if self.seen[var_of(self.trail[index])] { break; }
+            }
+
+            let lit = self.trail[index];
+            self.seen[var_of(lit)] = false;
+            implied = Some(lit);
+            pending -= 1;
+
+            if pending == 0 { break; }
+
+            clause = self.reason[var_of(lit)].expect("Implied literal without a reason");
+        }
+
+        learnt[0] = implied.unwrap() ^ 1;
+
+        for lit in &learnt[1..]
+        {
+            self.seen[var_of(*lit)] = false;
+        }
+
+        let mut backjump = 0;
+        if learnt.len() > 1
+        {
+            let mut max_position = 1;
+            for position in 2..learnt.len()
+            {
+                if self.level[var_of(learnt[position])] > self.level[var_of(learnt[max_position])]
+                {
+                    max_position = position;
+                }
+            }
+
+            learnt.swap(1, max_position);
+            backjump = self.level[var_of(learnt[1])];
+        }
+
+        return (learnt, backjump);
+    }
+
+    fn pick_branch_var(&self) -> Option<usize>
+    {
+        let mut best: Option<usize> = None;
+
+        for var in 0..self.num_vars
+        {
+            if self.assigns[var].is_some() { continue; }
+
+            match best
+            {
+                Some(current) if self.activity[current] >= self.activity[var] => (),
+                _ => { best = Some(var); }
+            }
+        }
+
+        return best;
+    }
+
+    /// The `i`-th element (counting from 1) of the Luby sequence 1, 1, 2, 1, 1, 2, 4, ...
+    fn luby(i: u64) -> u64
+    {
+        let mut k = 1;
+        while (1u64 << k) - 1 < i { k += 1; }
+
+        if (1u64 << k) - 1 == i
+        {
+            return 1 << (k - 1);
+        }
+
+        return Self::luby(i - (1u64 << (k - 1)) + 1);
+    }
+
+    pub fn solve(&mut self) -> SatResult
+    {
+        if self.unsat { return SatResult::Unsat; }
+
+        self.cancel_until(0);
+        if self.propagate().is_some()
+        {
+            self.unsat = true;
+            return SatResult::Unsat;
+        }
+
+        let mut restarts: u64 = 1;
+        let mut conflicts: u64 = 0;
+
+        loop {
+            if let Some(conflict) = self.propagate()
+            {
+                conflicts += 1;
+
+                if self.decision_level() == 0
+                {
+                    self.unsat = true;
+                    return SatResult::Unsat;
+                }
+
+                let (learnt, backjump) = self.analyze(conflict);
+                self.cancel_until(backjump);
+
+                if learnt.len() == 1
+                {
+                    self.enqueue(learnt[0], None);
+                }
+                else
+                {
+                    let asserting = learnt[0];
+                    let index = self.attach(learnt);
+                    self.enqueue(asserting, Some(index));
+                }
+
+                self.var_inc /= ACTIVITY_DECAY;
+                continue;
+            }
+
+            if conflicts >= RESTART_UNIT * Self::luby(restarts)
+            {
+                conflicts = 0;
+                restarts += 1;
+                self.cancel_until(0);
+                continue;
+            }
+
+            match self.pick_branch_var()
+            {
+                Some(var) => {
+                    self.trail_lim.push(self.trail.len());
+                    let lit = 2 * var + (!self.phase[var]) as usize;
+                    self.enqueue(lit, None);
+                },
+                None => {
+                    let model = self.assigns.iter().map(|val| val.unwrap()).collect();
+                    return SatResult::Sat(model);
+                }
+            }
+        }
+    }
+}
+
+pub fn solve(cnf: &Cnf) -> SatResult
+{
+    return Solver::from_cnf(cnf).solve();
+}
+
+/// Cuts a model of a Tseitin encoding down to the variables of the encoded formula
+fn project(result: SatResult, var_count: usize) -> Option<Vec<bool>>
+{
+    return match result
+    {
+        SatResult::Sat(mut model) => {
+            model.truncate(var_count);
+            Some(model)
+        },
+        SatResult::Unsat => None,
+    };
+}
+
+/// Returns an assignment of `formula.variables` that makes the formula true, if there is one
+pub fn find_model(formula: &Formula) -> Option<Vec<bool>>
+{
+    let var_count = formula.variables.len();
+    return project(solve(&cnf::tseitin(&formula.root, var_count)), var_count);
+}
+
+/// Returns an assignment that makes the formula false, there is none exactly when it is valid
+pub fn find_counterexample(formula: &Formula) -> Option<Vec<bool>>
+{
+    let var_count = formula.variables.len();
+
+    let mut encoder = TseitinEncoder::new(var_count);
+    let root = encoder.encode(&formula.root);
+    encoder.assert(-root);
+
+    return project(solve(&encoder.cnf), var_count);
+}
+
+/**
+ * Returns an assignment on which two formulas over shared variables (see `formula::parse_shared`)
+ * disagree, there is none exactly when they are equivalent
+**/
+pub fn find_difference(left: &Formula, right: &Formula) -> Option<Vec<bool>>
+{
+    return find_node_difference(&left.root, &right.root, left.variables.len().max(right.variables.len()));
+}
+
+/// Like `find_difference`, for two syntax trees over the first `var_count` variable locations
+pub fn find_node_difference(left: &ASTNode, right: &ASTNode, var_count: usize) -> Option<Vec<bool>>
+{
+    let mut encoder = TseitinEncoder::new(var_count);
+    let left_root = encoder.encode(left);
+    let right_root = encoder.encode(right);
+    encoder.cnf.clauses.push(vec![left_root, right_root]);
+    encoder.cnf.clauses.push(vec![-left_root, -right_root]);
+
+    return project(solve(&encoder.cnf), var_count);
+}
+
+/**
+ * Lists assignments that make the formula true, using the solver with a blocking clause after
+ * every model. Only the variables at the `projection` locations are reported and models that
+ * agree on them are listed once. Stops after `limit` models if given
+**/
+pub fn enumerate_models(formula: &Formula, projection: &[usize], limit: Option<usize>) -> Vec<Vec<bool>>
+{
+    let var_count = formula.variables.len();
+    let mut solver = Solver::from_cnf(&cnf::tseitin(&formula.root, var_count));
+    let mut models = Vec::<Vec<bool>>::new();
+
+    while limit.is_none_or(|limit| models.len() < limit)
+    {
+        let model = match solver.solve()
+        {
+            SatResult::Sat(model) => model,
+            SatResult::Unsat => break,
+        };
+
+        let blocking = projection.iter()
+            .map(|loc| if model[*loc] { -((loc + 1) as Lit) } else { (loc + 1) as Lit })
+            .collect::<Vec<Lit>>();
+        solver.add_clause(&blocking);
+
+        models.push(projection.iter().map(|loc| model[*loc]).collect());
+    }
+
+    return models;
+}
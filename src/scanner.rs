@@ -36,7 +36,7 @@ pub struct ScanState<'a>
 }
 
 impl<'a> ScanState<'a> {
-    fn init_from(stmt: & str) -> ScanState {
+    fn init_from(stmt: & str) -> ScanState<'_> {
         return ScanState {
             source: stmt,
             input_length: stmt.len(),
@@ -52,13 +52,14 @@ impl<'a> ScanState<'a> {
 
     fn peek(&self) -> char
     {
-        return self.source.as_bytes()[self.next_unobserved] as char;
+        return self.source[self.next_unobserved..].chars().next().unwrap_or('\0');
     }
 
     fn move_forward(&mut self) -> char
     {
         let ch = self.peek();
-        self.next_unobserved += 1;
+        // Step over the whole character so lexemes are always sliced on character boundaries
+        self.next_unobserved += ch.len_utf8();
         return ch;
     }
 
@@ -81,7 +82,7 @@ impl<'a> ScanState<'a> {
 }
 
 
-pub fn tokenize(stmt: &str) -> Vec<Token> {
+pub fn tokenize(stmt: &str) -> Vec<Token<'_>> {
 
     let keywords_map: HashMap<&str, OperatorType> = HashMap::from([
         ("and", OperatorType::AND),
@@ -0,0 +1,350 @@
+use crate::compiler::{ASTNode, NodeOperation};
+use crate::scanner::OperatorType;
+
+/// Default number of steps a simplification may take before it stops
+pub const DEFAULT_STEP_LIMIT: usize = 200;
+
+/// The laws of Boolean algebra a simplification step may apply
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Law
+{
+    /// `a => b` is `!a | b`
+    ImplicationElimination,
+    /// `a <=> b` is `(!a | b) & (a | !b)`
+    BiconditionalElimination,
+    /// `!!a` is `a`
+    DoubleNegation,
+    /// `!(a & b)` is `!a | !b` and `!(a | b)` is `!a & !b`
+    DeMorgan,
+    /// `a & !a` is false, `a | !a` is true and the negation of a constant is the other constant.
+    /// `!a` may also be written out by De Morgan, `(a & !b) | (!a | b)` is true
+    Complement,
+    /// `a & T` and `a | F` are `a`
+    Identity,
+    /// `a & F` is false and `a | T` is true
+    Domination,
+    /// `a & a` and `a | a` are `a`
+    Idempotence,
+    /// `a & (a | b)` is `a` and `a & (!a | b)` is `a & b`, likewise with `&` and `|` swapped. As
+    /// for `Complement`, `(a & !b) | ((!a | b) & c)` is `(a & !b) | c`
+    Absorption,
+    /// `(a & b) | (a & c)` is `a & (b | c)` and `(a | b) & (a | c)` is `a | (b & c)`
+    Distribution,
+}
+
+/// Every law, in the order they are tried: implications go first, then negations are pushed inwards
+pub const LAWS: [Law; 10] = [
+    Law::ImplicationElimination,
+    Law::BiconditionalElimination,
+    Law::DoubleNegation,
+    Law::DeMorgan,
+    Law::Complement,
+    Law::Identity,
+    Law::Domination,
+    Law::Idempotence,
+    Law::Absorption,
+    Law::Distribution,
+];
+
+impl std::fmt::Display for Law
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self
+        {
+            Law::ImplicationElimination => write!(f, "Implication elimination"),
+            Law::BiconditionalElimination => write!(f, "Biconditional elimination"),
+            Law::DoubleNegation => write!(f, "Double negation"),
+            Law::DeMorgan => write!(f, "De Morgan"),
+            Law::Complement => write!(f, "Complement"),
+            Law::Identity => write!(f, "Identity"),
+            Law::Domination => write!(f, "Domination"),
+            Law::Idempotence => write!(f, "Idempotence"),
+            Law::Absorption => write!(f, "Absorption"),
+            Law::Distribution => write!(f, "Distribution"),
+        }
+    }
+}
+
+/// One application of a law and the whole formula after it
+#[derive(Clone, Debug)]
+pub struct Step
+{
+    pub law: Law,
+    pub result: Box<ASTNode>,
+}
+
+fn child(node: &ASTNode, right: bool) -> &ASTNode
+{
+    let child = if right { &node.right } else { &node.left };
+    return child.as_ref().expect("Operand not found");
+}
+
+/// Copy of the tree without the grouping nodes of braces and parentheses
+fn strip(node: &ASTNode) -> Box<ASTNode>
+{
+    return match node.op
+    {
+        NodeOperation::Subexpression | NodeOperation::IndexedSubexpression(_) => strip(child(node, false)),
+        _ => Box::new(ASTNode {
+            op: node.op,
+            left: node.left.as_ref().map(|left| strip(left)),
+            right: node.right.as_ref().map(|right| strip(right)),
+        }),
+    };
+}
+
+fn same(left: &ASTNode, right: &ASTNode) -> bool
+{
+    let same_child = |left: &Option<Box<ASTNode>>, right: &Option<Box<ASTNode>>| match (left, right)
+    {
+        (Some(left), Some(right)) => same(left, right),
+        (None, None) => true,
+        _ => false,
+    };
+
+    return left.op == right.op && same_child(&left.left, &right.left) && same_child(&left.right, &right.right);
+}
+
+fn negated(node: &ASTNode) -> Option<&ASTNode>
+{
+    return match node.op
+    {
+        NodeOperation::UnaryOperation(_) => Some(child(node, false)),
+        _ => None,
+    };
+}
+
+/**
+ * Whether one of the nodes is the negation of the other, either written with `!` or, by De Morgan,
+ * as a chain of the dual operator over the complements of its operands: `a & !b` and `!a | b`
+**/
+fn complementary(left: &ASTNode, right: &ASTNode) -> bool
+{
+    if negated(left).is_some_and(|inner| same(inner, right)) || negated(right).is_some_and(|inner| same(inner, left))
+    {
+        return true;
+    }
+
+    let (Some((left_op, _, _)), Some((right_op, _, _))) = (binary(left), binary(right)) else { return false; };
+    let duals = matches!(
+        (left_op, right_op),
+        (OperatorType::AND, OperatorType::OR) | (OperatorType::OR, OperatorType::AND)
+    );
+    if !duals { return false; }
+
+    let (left, right) = (operands_of(left, left_op), operands_of(right, right_op));
+    return left.len() == right.len()
+        && left.iter().all(|left| right.iter().any(|right| complementary(left, right)))
+        && right.iter().all(|right| left.iter().any(|left| complementary(left, right)));
+}
+
+fn binary(node: &ASTNode) -> Option<(OperatorType, &ASTNode, &ASTNode)>
+{
+    return match node.op
+    {
+        NodeOperation::BinaryOperation(op) => Some((op, child(node, false), child(node, true))),
+        _ => None,
+    };
+}
+
+fn literal(node: &ASTNode) -> Option<bool>
+{
+    return match node.op
+    {
+        NodeOperation::Literal(val) => Some(val),
+        _ => None,
+    };
+}
+
+fn copy(node: &ASTNode) -> Box<ASTNode>
+{
+    return Box::new(node.clone());
+}
+
+fn not(node: &ASTNode) -> Box<ASTNode>
+{
+    return ASTNode::unary(OperatorType::NOT, copy(node));
+}
+
+/// Operands of a chain of the same operator, `a & (b & c)` gives `a`, `b` and `c`
+fn flatten<'a>(node: &'a ASTNode, op: OperatorType, operands: &mut Vec<&'a ASTNode>)
+{
+    match binary(node)
+    {
+        Some((node_op, left, right)) if node_op == op => {
+            flatten(left, op, operands);
+            flatten(right, op, operands);
+        },
+        _ => operands.push(node),
+    }
+}
+
+fn operands_of(node: &ASTNode, op: OperatorType) -> Vec<&ASTNode>
+{
+    let mut operands = Vec::<&ASTNode>::new();
+    flatten(node, op, &mut operands);
+    return operands;
+}
+
+/// Joins the operands of a chain again, with the one at `removed` left out and the one at `replaced` swapped
+fn rejoin(op: OperatorType, operands: &[&ASTNode], replaced: Option<(usize, Box<ASTNode>)>, removed: Option<usize>) -> Box<ASTNode>
+{
+    let mut replaced = replaced;
+
+    return (0..operands.len())
+        .filter(|index| removed != Some(*index))
+        .map(|index| match replaced.take_if(|(at, _)| *at == index)
+        {
+            Some((_, node)) => node,
+            None => copy(operands[index]),
+        })
+        .reduce(|left, right| ASTNode::binary(op, left, right))
+        .expect("Chain without operands");
+}
+
+/**
+ * The laws on a chain of AND or of OR. They look at any two operands of the chain, so neither its
+ * grouping nor the order of its operands keep a law from applying
+**/
+fn apply_chain_law(node: &ASTNode, op: OperatorType, law: Law) -> Option<Box<ASTNode>>
+{
+    let or = op == OperatorType::OR;
+    let dual = if or { OperatorType::AND } else { OperatorType::OR };
+
+    let operands = operands_of(node, op);
+    let count = operands.len();
+    let pairs = (0..count).flat_map(|first| (0..count).filter(move |second| *second != first).map(move |second| (first, second)));
+
+    return match law
+    {
+        Law::Complement => pairs.clone().any(|(first, second)| complementary(operands[first], operands[second])).then(|| ASTNode::literal(or)),
+        // The neutral constant is true for AND and false for OR, the dominating one the other way around
+        Law::Identity => operands.iter()
+            .position(|operand| literal(operand) == Some(!or))
+            .map(|index| rejoin(op, &operands, None, Some(index))),
+        Law::Domination => operands.iter().any(|operand| literal(operand) == Some(or)).then(|| ASTNode::literal(or)),
+        Law::Idempotence => pairs.clone()
+            .find(|(first, second)| first < second && same(operands[*first], operands[*second]))
+            .map(|(_, second)| rejoin(op, &operands, None, Some(second))),
+        Law::Absorption => {
+            // `a & (a | b)` is `a`, and `a & (!a | b)` is `a & b` since `!a` cannot hold next to `a`.
+            // `a` can be a chain itself, and `!a` its De Morgan complement
+            let inner = operands.iter().map(|operand| operands_of(operand, dual)).collect::<Vec<Vec<&ASTNode>>>();
+
+            for (kept, other) in pairs.filter(|(_, other)| inner[*other].len() > 1)
+            {
+                if inner[other].iter().any(|operand| same(operand, operands[kept]))
+                {
+                    return Some(rejoin(op, &operands, None, Some(other)));
+                }
+
+                if let Some(index) = inner[other].iter().position(|operand| complementary(operand, operands[kept]))
+                {
+                    let reduced = rejoin(dual, &inner[other], None, Some(index));
+                    return Some(rejoin(op, &operands, Some((other, reduced)), None));
+                }
+            }
+
+            None
+        },
+        Law::Distribution => {
+            // `(a & b) | (a & c)` is `a & (b | c)`, the common operand is factored out
+            let inner = operands.iter().map(|operand| operands_of(operand, dual)).collect::<Vec<Vec<&ASTNode>>>();
+
+            for (first, second) in pairs.filter(|(first, second)| first < second && inner[*first].len() > 1 && inner[*second].len() > 1)
+            {
+                for (at_first, common) in inner[first].iter().enumerate()
+                {
+                    let Some(at_second) = inner[second].iter().position(|operand| same(operand, common)) else { continue; };
+
+                    let rest = ASTNode::binary(
+                        op,
+                        rejoin(dual, &inner[first], None, Some(at_first)),
+                        rejoin(dual, &inner[second], None, Some(at_second)),
+                    );
+                    let factored = ASTNode::binary(dual, copy(common), rest);
+
+                    return Some(rejoin(op, &operands, Some((first, factored)), Some(second)));
+                }
+            }
+
+            None
+        },
+        _ => None,
+    };
+}
+
+/// The result of applying `law` at the root of `node`, `None` when it does not apply there
+fn apply_law(node: &ASTNode, law: Law) -> Option<Box<ASTNode>>
+{
+    if let Some(inner) = negated(node)
+    {
+        return match (law, inner.op)
+        {
+            (Law::DoubleNegation, NodeOperation::UnaryOperation(_)) => Some(copy(child(inner, false))),
+            (Law::DeMorgan, NodeOperation::BinaryOperation(op @ (OperatorType::AND | OperatorType::OR))) => {
+                let dual = if op == OperatorType::AND { OperatorType::OR } else { OperatorType::AND };
+                Some(ASTNode::binary(dual, not(child(inner, false)), not(child(inner, true))))
+            },
+            (Law::Complement, NodeOperation::Literal(val)) => Some(ASTNode::literal(!val)),
+            _ => None,
+        };
+    }
+
+    let (op, left, right) = binary(node)?;
+
+    return match law
+    {
+        Law::ImplicationElimination if op == OperatorType::CNDL => Some(ASTNode::binary(OperatorType::OR, not(left), copy(right))),
+        Law::BiconditionalElimination if op == OperatorType::BI_CNDL => Some(ASTNode::binary(
+            OperatorType::AND,
+            ASTNode::binary(OperatorType::OR, not(left), copy(right)),
+            ASTNode::binary(OperatorType::OR, copy(left), not(right)),
+        )),
+        _ if op == OperatorType::AND || op == OperatorType::OR => apply_chain_law(node, op, law),
+        _ => None,
+    };
+}
+
+/// Applies `law` once, at the outermost and then leftmost place of the tree where it applies
+fn rewrite_once(node: &ASTNode, law: Law) -> Option<Box<ASTNode>>
+{
+    if let Some(result) = apply_law(node, law) { return Some(result); }
+
+    if let Some(left) = node.left.as_ref().and_then(|left| rewrite_once(left, law))
+    {
+        return Some(Box::new(ASTNode { op: node.op, left: Some(left), right: node.right.clone() }));
+    }
+
+    if let Some(right) = node.right.as_ref().and_then(|right| rewrite_once(right, law))
+    {
+        return Some(Box::new(ASTNode { op: node.op, left: node.left.clone(), right: Some(right) }));
+    }
+
+    return None;
+}
+
+/**
+ * Simplifies a formula one law at a time and returns every step. Each step applies the first law
+ * of `LAWS` that applies anywhere in the formula, so implications are eliminated and negations
+ * pushed down to the variables before the simplifying laws take over. Stops when no law applies
+ * or after `limit` steps. Every law keeps the formula equivalent
+**/
+pub fn simplify(node: &ASTNode, limit: usize) -> Vec<Step>
+{
+    let mut steps = Vec::<Step>::new();
+    let mut current = strip(node);
+
+    while steps.len() < limit
+    {
+        let Some((law, result)) = LAWS.iter().find_map(|law| rewrite_once(&current, *law).map(|result| (*law, result))) else {
+            break;
+        };
+
+        current = result.clone();
+        steps.push(Step { law, result });
+    }
+
+    return steps;
+}
@@ -0,0 +1,304 @@
+use crate::compiler::NodeOperation;
+use crate::bytecode::{self, Program};
+use crate::execution::{self, EvalError};
+use std::thread;
+
+/// Largest number of variables a table can be built for, row indices are `u32`
+pub const MAX_VARIABLES: u32 = 31;
+
+/// Tables smaller than this are always evaluated on the calling thread, spawning
+/// workers for them costs more than it saves
+const MIN_ROWS_PER_THREAD: u32 = 4096;
+
+pub fn pow2(exponent: u32) -> u32
+{
+    return 2u32.pow(exponent);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum RowOrder
+{
+    /// Counts up in binary, starting from the all-false row
+    Binary,
+    /// Counts down in binary, starting from the all-true row
+    #[default]
+    AllTrueFirst,
+    /// Reflected Gray code starting from the all-false row, consecutive rows differ in exactly one variable
+    Gray,
+}
+
+/// The order in which rows of a table are produced
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct RowOrdering
+{
+    pub order: RowOrder,
+    /// Produce the rows of `order` back to front
+    pub reversed: bool,
+}
+
+impl RowOrdering
+{
+    /**
+     * Returns the minterm index of the row at `position` in a table of `size` variables. In a
+     * minterm index the first variable is the most significant bit and a set bit means true
+    **/
+    pub fn minterm_at(&self, size: u32, position: u32) -> u32
+    {
+        let last = pow2(size) - 1;
+        let position = if self.reversed { last - position } else { position };
+
+        return match self.order
+        {
+            RowOrder::Binary => position,
+            RowOrder::AllTrueFirst => last - position,
+            RowOrder::Gray => position ^ (position >> 1),
+        };
+    }
+}
+
+/// Expands a minterm index (see `RowOrdering::minterm_at`) into the values of `size` variables
+pub fn minterm_values(size: u32, minterm: u32) -> Vec<bool>
+{
+    return (0..size).map(|col| (minterm >> (size - col - 1)) & 1 == 1).collect();
+}
+
+pub struct BoolPermutationsIterator
+{
+    data: Vec<bool>,
+    pub size: u32,
+    ordering: RowOrdering,
+    current: u32,
+    last: u32,
+}
+
+impl BoolPermutationsIterator
+{
+    pub fn new(size: u32) -> Self
+    {
+        Self::with_range(size, RowOrdering::default(), 0, pow2(size))
+    }
+
+    pub fn with_ordering(size: u32, ordering: RowOrdering) -> Self
+    {
+        Self::with_range(size, ordering, 0, pow2(size))
+    }
+
+    /**
+     * Iterates only over the rows in `first..last`. Row `i` is the same assignment `with_ordering()`
+     * would produce at its `i`-th step
+    **/
+    pub fn with_range(size: u32, ordering: RowOrdering, first: u32, last: u32) -> Self
+    {
+        Self {
+            data: vec![false; size as usize],
+            size,
+            ordering,
+            current: first,
+            last
+        }
+    }
+
+    pub fn get(&self) -> &Vec<bool>
+    {
+        return &self.data;
+    }
+
+    pub fn finished(&self) -> bool
+    {
+        return self.current >= self.last;
+    }
+
+    pub fn advance(&mut self)
+    {
+        let minterm = self.ordering.minterm_at(self.size, self.current);
+        for col in 0..self.size
+        {
+            self.data[col as usize] = (minterm >> (self.size - col - 1)) & 1 == 1;
+        }
+        self.current += 1;
+    }
+}
+
+/**
+ * Evaluated rows of a truth table. `inputs[i]` is the assignment of the i-th row and
+ * `outputs[i]` holds the value of every subexpression group for that assignment
+**/
+pub struct TruthTable
+{
+    pub inputs: Vec<Vec<bool>>,
+    pub outputs: Vec<Vec<bool>>,
+}
+
+impl TruthTable
+{
+    /**
+     * The last column (the whole formula) indexed by minterm, like `truth_vector`, whatever the
+     * order the rows were evaluated in
+    **/
+    pub fn result_vector(&self) -> Vec<bool>
+    {
+        let mut truth = vec![false; self.outputs.len()];
+        for (inputs, outputs) in self.inputs.iter().zip(self.outputs.iter())
+        {
+            let minterm = inputs.iter().fold(0usize, |minterm, value| (minterm << 1) | *value as usize);
+            truth[minterm] = outputs[outputs.len() - 1];
+        }
+
+        return truth;
+    }
+}
+
+fn evaluate_range(program: &Program, var_count: u32, ordering: RowOrdering, first: u32, last: u32) -> TruthTable
+{
+    let mut table = TruthTable {
+        inputs: Vec::with_capacity((last - first) as usize),
+        outputs: Vec::with_capacity((last - first) as usize),
+    };
+
+    if first >= last { return table; }
+
+    let mut iter = BoolPermutationsIterator::with_range(var_count, ordering, first, last);
+    let mut row_results = vec![false; program.outputs.len()];
+    let mut registers = vec![false; program.register_count];
+
+    loop {
+        iter.advance();
+
+        program.evaluate(iter.get(), &mut registers[..], &mut row_results[..]);
+
+        table.inputs.push(iter.get().clone());
+        table.outputs.push(row_results.clone());
+
+        if iter.finished() {
+            break;
+        }
+    }
+
+    return table;
+}
+
+/**
+ * Evaluates every row of the table in the given order, splitting the rows into contiguous
+ * chunks across at most `threads` worker threads. Chunks are merged back in row order, so
+ * the result does not depend on the number of threads used
+**/
+pub fn evaluate_table(groups: &[Vec<NodeOperation>], var_count: u32, ordering: RowOrdering, threads: usize) -> Result<TruthTable, EvalError>
+{
+    if var_count > MAX_VARIABLES
+    {
+        return Err(EvalError::TooManyVariables { count: var_count as usize, max: MAX_VARIABLES as usize });
+    }
+
+    execution::validate_groups(groups, var_count as usize)?;
+
+    let program = bytecode::compile(groups)?;
+    let program = &program;
+
+    let total = pow2(var_count);
+    let max_threads = ((total / MIN_ROWS_PER_THREAD) as usize).max(1);
+    let threads = threads.clamp(1, max_threads) as u32;
+
+    if threads == 1
+    {
+        return Ok(evaluate_range(program, var_count, ordering, 0, total));
+    }
+
+    let chunk_size = total.div_ceil(threads);
+
+    let chunks: Vec<TruthTable> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|i| {
+                let first = i * chunk_size;
+                let last = (first + chunk_size).min(total);
+                scope.spawn(move || evaluate_range(program, var_count, ordering, first, last))
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("Worker thread panicked"))
+            .collect()
+    });
+
+    let mut table = TruthTable {
+        inputs: Vec::with_capacity(total as usize),
+        outputs: Vec::with_capacity(total as usize),
+    };
+
+    for chunk in chunks
+    {
+        table.inputs.extend(chunk.inputs);
+        table.outputs.extend(chunk.outputs);
+    }
+
+    return Ok(table);
+}
+
+fn truth_range(program: &Program, var_count: usize, rows: usize, first: u64, last: u64) -> Vec<bool>
+{
+    let result = program.outputs.len() - 1;
+    let mut values = vec![0u64; var_count];
+    let mut registers = vec![0u64; program.register_count];
+    let mut out_eval = vec![0u64; program.outputs.len()];
+    let mut truth = Vec::with_capacity(((last - first) as usize * 64).min(rows));
+
+    for block in first..last
+    {
+        bytecode::load_block(&mut values, block);
+        program.evaluate_words(&values, &mut registers, &mut out_eval);
+
+        let word = out_eval[result];
+        truth.extend((0..64.min(rows)).map(|lane| (word >> lane) & 1 == 1));
+    }
+
+    return truth;
+}
+
+/**
+ * Evaluates the last subexpression group (the whole formula) for every assignment and returns
+ * the results indexed by minterm (see `RowOrdering::minterm_at`). Only the formula's own column
+ * is computed, 64 rows at a time
+**/
+pub fn truth_vector(groups: &[Vec<NodeOperation>], var_count: u32, threads: usize) -> Result<Vec<bool>, EvalError>
+{
+    if var_count > MAX_VARIABLES
+    {
+        return Err(EvalError::TooManyVariables { count: var_count as usize, max: MAX_VARIABLES as usize });
+    }
+
+    execution::validate_groups(groups, var_count as usize)?;
+
+    let program = bytecode::compile(groups)?;
+    let program = &program;
+
+    let rows = pow2(var_count) as usize;
+    let blocks = rows.div_ceil(64) as u64;
+    let var_count = var_count as usize;
+
+    let max_threads = (blocks / (MIN_ROWS_PER_THREAD as u64 / 64)).max(1);
+    let threads = (threads as u64).clamp(1, max_threads);
+
+    if threads == 1
+    {
+        return Ok(truth_range(program, var_count, rows, 0, blocks));
+    }
+
+    let chunk_size = blocks.div_ceil(threads);
+
+    let chunks: Vec<Vec<bool>> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|i| {
+                let first = i * chunk_size;
+                let last = (first + chunk_size).min(blocks);
+                scope.spawn(move || truth_range(program, var_count, rows, first, last))
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("Worker thread panicked"))
+            .collect()
+    });
+
+    return Ok(chunks.concat());
+}
@@ -0,0 +1,266 @@
+use crate::canonical::Term;
+use crate::compiler::{ASTNode, NodeOperation};
+use crate::scanner::OperatorType;
+
+/// Default number of nodes (or terms) a conversion may produce before giving up
+pub const DEFAULT_SIZE_LIMIT: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransformError
+{
+    /// The converted formula would be larger than the given limit
+    TooLarge { limit: usize },
+}
+
+impl std::fmt::Display for TransformError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self
+        {
+            TransformError::TooLarge { limit } => write!(f, "Conversion exceeds the size limit of {}", limit),
+        }
+    }
+}
+
+impl std::error::Error for TransformError {}
+
+/// Counts down the nodes a conversion may still create
+struct Budget
+{
+    remaining: usize,
+    limit: usize,
+}
+
+impl Budget
+{
+    fn new(limit: usize) -> Self
+    {
+        Self { remaining: limit, limit }
+    }
+
+    fn spend(&mut self, amount: usize) -> Result<(), TransformError>
+    {
+        if amount > self.remaining
+        {
+            return Err(TransformError::TooLarge { limit: self.limit });
+        }
+
+        self.remaining -= amount;
+        return Ok(());
+    }
+}
+
+fn child(node: &ASTNode, right: bool) -> &ASTNode
+{
+    let child = if right { &node.right } else { &node.left };
+    return child.as_ref().expect("Operand not found");
+}
+
+fn eliminate_impl(node: &ASTNode, budget: &mut Budget) -> Result<Box<ASTNode>, TransformError>
+{
+    budget.spend(1)?;
+
+    let result = match node.op
+    {
+        NodeOperation::VariableDeref(loc) => ASTNode::variable(loc),
+        NodeOperation::Literal(val) => ASTNode::literal(val),
+        NodeOperation::Subexpression | NodeOperation::IndexedSubexpression(_) => {
+            return eliminate_impl(child(node, false), budget);
+        },
+        NodeOperation::UnaryOperation(op) => ASTNode::unary(op, eliminate_impl(child(node, false), budget)?),
+        NodeOperation::BinaryOperation(op) => {
+            let left = eliminate_impl(child(node, false), budget)?;
+            let right = eliminate_impl(child(node, true), budget)?;
+
+            match op
+            {
+                OperatorType::CNDL => ASTNode::binary(OperatorType::OR, ASTNode::unary(OperatorType::NOT, left), right),
+                OperatorType::BI_CNDL => {
+                    // Both sides appear twice, charge the copies as well
+                    budget.spend(4 + size(&left) + size(&right))?;
+                    ASTNode::binary(
+                        OperatorType::AND,
+                        ASTNode::binary(OperatorType::OR, ASTNode::unary(OperatorType::NOT, left.clone()), right.clone()),
+                        ASTNode::binary(OperatorType::OR, left, ASTNode::unary(OperatorType::NOT, right)),
+                    )
+                },
+                _ => ASTNode::binary(op, left, right),
+            }
+        },
+    };
+
+    return Ok(result);
+}
+
+fn size(node: &ASTNode) -> usize
+{
+    return 1
+        + node.left.as_ref().map(|child| size(child)).unwrap_or(0)
+        + node.right.as_ref().map(|child| size(child)).unwrap_or(0);
+}
+
+/**
+ * Rewrites `a => b` as `!a | b` and `a <=> b` as `(!a | b) & (a | !b)`, leaving only AND, OR
+ * and NOT. Fails if the result would have more than `limit` nodes
+**/
+pub fn eliminate_implications(node: &ASTNode, limit: usize) -> Result<Box<ASTNode>, TransformError>
+{
+    return eliminate_impl(node, &mut Budget::new(limit));
+}
+
+/// Negation normal form of `node`, or of its negation when `negate` is set
+fn nnf_impl(node: &ASTNode, negate: bool, budget: &mut Budget) -> Result<Box<ASTNode>, TransformError>
+{
+    budget.spend(1)?;
+
+    let (and, or) = if negate { (OperatorType::OR, OperatorType::AND) } else { (OperatorType::AND, OperatorType::OR) };
+
+    let result = match node.op
+    {
+        NodeOperation::VariableDeref(loc) if negate => ASTNode::unary(OperatorType::NOT, ASTNode::variable(loc)),
+        NodeOperation::VariableDeref(loc) => ASTNode::variable(loc),
+        NodeOperation::Literal(val) => ASTNode::literal(val != negate),
+        NodeOperation::Subexpression | NodeOperation::IndexedSubexpression(_) => {
+            return nnf_impl(child(node, false), negate, budget);
+        },
+        NodeOperation::UnaryOperation(_) => {
+            return nnf_impl(child(node, false), !negate, budget);
+        },
+        NodeOperation::BinaryOperation(op) => {
+            let (a, b) = (child(node, false), child(node, true));
+
+            match op
+            {
+                // De Morgan when negated
+                OperatorType::AND => ASTNode::binary(and, nnf_impl(a, negate, budget)?, nnf_impl(b, negate, budget)?),
+                OperatorType::OR => ASTNode::binary(or, nnf_impl(a, negate, budget)?, nnf_impl(b, negate, budget)?),
+                // a => b is !a | b
+                OperatorType::CNDL => ASTNode::binary(or, nnf_impl(a, !negate, budget)?, nnf_impl(b, negate, budget)?),
+                // a <=> b is (!a | b) & (a | !b), its negation (a | b) & (!a | !b)
+                OperatorType::BI_CNDL => ASTNode::binary(
+                    OperatorType::AND,
+                    ASTNode::binary(OperatorType::OR, nnf_impl(a, true, budget)?, nnf_impl(b, negate, budget)?),
+                    ASTNode::binary(OperatorType::OR, nnf_impl(a, false, budget)?, nnf_impl(b, !negate, budget)?),
+                ),
+                OperatorType::NOT => unreachable!("NOT is not a binary operator"),
+            }
+        },
+    };
+
+    return Ok(result);
+}
+
+/**
+ * Converts to negation normal form: implications are eliminated and negations are pushed down
+ * until they only apply to variables. Fails if the result would have more than `limit` nodes
+**/
+pub fn to_nnf(node: &ASTNode, limit: usize) -> Result<Box<ASTNode>, TransformError>
+{
+    return nnf_impl(node, false, &mut Budget::new(limit));
+}
+
+/// Merges two terms, `None` when they contain complementary literals
+fn merge_terms(left: &Term, right: &Term) -> Option<Term>
+{
+    let mut merged = left.clone();
+
+    for literal in right
+    {
+        if merged.contains(&(literal.0, !literal.1)) { return None; }
+        if !merged.contains(literal) { merged.push(*literal); }
+    }
+
+    merged.sort_unstable();
+    return Some(merged);
+}
+
+/// Drops duplicate terms and every term that contains all literals of another term
+fn remove_subsumed(terms: Vec<Term>) -> Vec<Term>
+{
+    let mut terms = terms;
+    terms.sort_by_key(|term| term.len());
+
+    let mut kept: Vec<Term> = vec![];
+    for term in terms
+    {
+        if !kept.iter().any(|smaller| smaller.iter().all(|literal| term.contains(literal)))
+        {
+            kept.push(term);
+        }
+    }
+
+    return kept;
+}
+
+/**
+ * Distributes an NNF tree into a list of terms. For CNF (`conjunctive`) the terms are clauses
+ * joined by AND, for DNF they are products joined by OR. Clauses with complementary literals
+ * are always true and products with them always false, so both are dropped
+**/
+fn distribute(node: &ASTNode, conjunctive: bool, limit: usize) -> Result<Vec<Term>, TransformError>
+{
+    let (join, cross) = if conjunctive { (OperatorType::AND, OperatorType::OR) } else { (OperatorType::OR, OperatorType::AND) };
+
+    let terms = match node.op
+    {
+        NodeOperation::VariableDeref(loc) => vec![vec![(loc, true)]],
+        NodeOperation::UnaryOperation(_) => match child(node, false).op
+        {
+            NodeOperation::VariableDeref(loc) => vec![vec![(loc, false)]],
+            _ => unreachable!("Negations in NNF only apply to variables"),
+        },
+        // An empty clause is false and an empty product true
+        NodeOperation::Literal(val) if val == conjunctive => vec![],
+        NodeOperation::Literal(_) => vec![vec![]],
+        NodeOperation::BinaryOperation(op) if op == join => {
+            let mut terms = distribute(child(node, false), conjunctive, limit)?;
+            terms.extend(distribute(child(node, true), conjunctive, limit)?);
+            terms
+        },
+        NodeOperation::BinaryOperation(op) if op == cross => {
+            let left = distribute(child(node, false), conjunctive, limit)?;
+            let right = distribute(child(node, true), conjunctive, limit)?;
+
+            if left.len() * right.len() > limit
+            {
+                return Err(TransformError::TooLarge { limit });
+            }
+
+            left.iter()
+                .flat_map(|a| right.iter().filter_map(move |b| merge_terms(a, b)))
+                .collect()
+        },
+        _ => unreachable!("Only AND, OR and NOT remain in NNF"),
+    };
+
+    // Subsumption is only checked once at the end, here duplicates are enough to keep the size down
+    let mut terms = terms;
+    terms.sort_unstable();
+    terms.dedup();
+
+    if terms.len() > limit
+    {
+        return Err(TransformError::TooLarge { limit });
+    }
+
+    return Ok(terms);
+}
+
+/**
+ * Conjunctive normal form by distributing OR over AND, as a list of clauses (see
+ * `canonical::render_product_of_sums`). Fails if more than `limit` clauses would be needed
+**/
+pub fn to_cnf(node: &ASTNode, limit: usize) -> Result<Vec<Term>, TransformError>
+{
+    return Ok(remove_subsumed(distribute(&*to_nnf(node, limit)?, true, limit)?));
+}
+
+/**
+ * Disjunctive normal form by distributing AND over OR, as a list of products (see
+ * `canonical::render_sum_of_products`). Fails if more than `limit` products would be needed
+**/
+pub fn to_dnf(node: &ASTNode, limit: usize) -> Result<Vec<Term>, TransformError>
+{
+    return Ok(remove_subsumed(distribute(&*to_nnf(node, limit)?, false, limit)?));
+}
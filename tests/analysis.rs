@@ -0,0 +1,185 @@
+//! Column summaries, assignments, distinguishing assignments and argument checks on small
+//! known formulas
+
+use ttbl::analysis::{self, Argument, Classification, ColumnSummary, Difference, RowKind};
+use ttbl::formula::{self, Formula, FormulaError};
+use ttbl::table::{self, RowOrdering};
+
+fn summaries(source: &str) -> Vec<ColumnSummary>
+{
+    let formula = Formula::parse(source).unwrap();
+    let table = table::evaluate_table(&formula.groups, formula.variables.len() as u32, RowOrdering::default(), 1).unwrap();
+    analysis::classify_table(&table)
+}
+
+fn summary(classification: Classification, true_rows: usize, false_rows: usize) -> ColumnSummary
+{
+    ColumnSummary { classification, true_rows, false_rows }
+}
+
+#[test]
+fn classifies_result_columns()
+{
+    assert_eq!(summaries("p | !p"), vec![summary(Classification::Tautology, 2, 0)]);
+    assert_eq!(summaries("p & !p"), vec![summary(Classification::Contradiction, 0, 2)]);
+    assert_eq!(summaries("p => q"), vec![summary(Classification::Contingent, 3, 1)]);
+    assert_eq!(summaries("(p <=> q) <=> r"), vec![summary(Classification::Contingent, 4, 4)]);
+
+    // Constants have a single row
+    assert_eq!(summaries("T"), vec![summary(Classification::Tautology, 1, 0)]);
+    assert_eq!(summaries("F"), vec![summary(Classification::Contradiction, 0, 1)]);
+}
+
+#[test]
+fn classifies_every_braced_subexpression()
+{
+    // A contradiction inside a tautology, each column is summarized on its own
+    assert_eq!(
+        summaries("{p & !p} => q"),
+        vec![summary(Classification::Contradiction, 0, 4), summary(Classification::Tautology, 4, 0)]
+    );
+
+    let contingent = summaries("{a & b} | {c & !c}");
+    assert_eq!(contingent[0], summary(Classification::Contingent, 2, 6));
+    assert_eq!(contingent[1], summary(Classification::Contradiction, 0, 8));
+    assert_eq!(contingent[2], summary(Classification::Contingent, 2, 6));
+}
+
+#[test]
+fn summaries_report_satisfiability()
+{
+    assert!(summary(Classification::Tautology, 2, 0).is_satisfiable());
+    assert!(summary(Classification::Contingent, 1, 3).is_satisfiable());
+    assert!(!summary(Classification::Contradiction, 0, 4).is_satisfiable());
+
+    assert_eq!(summaries("p => q")[0].to_string(), "contingent, satisfiable (3 true, 1 false)");
+    assert_eq!(summaries("p & !p")[0].to_string(), "contradiction, unsatisfiable (0 true, 2 false)");
+}
+
+#[test]
+fn parses_assignments()
+{
+    let parsed = formula::parse_assignment("p=T q=false  r1=1 s=f v=TRUE u=0").unwrap();
+    let expected = [("p", true), ("q", false), ("r1", true), ("s", false), ("v", true), ("u", false)];
+    assert_eq!(parsed, expected.iter().map(|(name, value)| (String::from(*name), *value)).collect::<Vec<(String, bool)>>());
+
+    assert_eq!(formula::parse_assignment("").unwrap(), vec![]);
+
+    for entry in ["p", "p=", "=T", "p=yes", "p=2", "p==T", "1=T", "p&q=T", "T=F", "p=T=F"]
+    {
+        assert_eq!(
+            formula::parse_assignment(&format!("q=T {}", entry)),
+            Err(FormulaError::InvalidAssignment(String::from(entry))),
+            "{}", entry
+        );
+    }
+}
+
+#[test]
+fn evaluates_assignments()
+{
+    let formula = Formula::parse("{p & q} => r").unwrap();
+    let assign = |source: &str| formula.evaluate_assignment(&formula::parse_assignment(source).unwrap());
+
+    assert_eq!(assign("p=T q=T r=F"), Ok(vec![true, false]));
+    assert_eq!(assign("r=T q=F p=T"), Ok(vec![false, true]));
+    // The last value given to a variable wins
+    assert_eq!(assign("p=F q=T r=F p=T"), Ok(vec![true, false]));
+
+    assert_eq!(assign("p=T q=T"), Err(FormulaError::MissingVariable(String::from("r"))));
+    assert_eq!(assign("p=T q=T r=T s=T"), Err(FormulaError::UnknownVariable(String::from("s"))));
+}
+
+fn differences(left: &str, right: &str) -> (Vec<String>, Vec<Difference>)
+{
+    let formulas = formula::parse_shared(&[left, right]).unwrap();
+    let ordering = RowOrdering { order: table::RowOrder::Binary, reversed: false };
+    let differences = analysis::distinguishing_assignments(&formulas[0], &formulas[1], ordering, 1).unwrap();
+    (formulas[0].variables.clone(), differences)
+}
+
+fn difference(assignment: &[bool], left: bool) -> Difference
+{
+    Difference { assignment: assignment.to_vec(), left }
+}
+
+#[test]
+fn lists_distinguishing_assignments()
+{
+    assert!(differences("p => q", "!p | q").1.is_empty());
+    assert!(differences("!(p & q)", "!q | !p").1.is_empty());
+
+    assert_eq!(differences("p => q", "q => p").1, vec![difference(&[false, true], true), difference(&[true, false], false)]);
+    assert_eq!(differences("T", "F").1, vec![difference(&[], true)]);
+}
+
+#[test]
+fn distinguishes_over_variables_of_either_formula()
+{
+    // `q` only appears on the left, `r` only on the right, both formulas are compared over all three
+    let (variables, found) = differences("p & q", "p & r");
+    assert_eq!(variables, vec!["p", "q", "r"]);
+    assert_eq!(found, vec![difference(&[true, false, true], false), difference(&[true, true, false], true)]);
+
+    // A variable the result does not depend on does not make formulas differ
+    let (variables, found) = differences("p", "p & (q | !q)");
+    assert_eq!(variables, vec!["p", "q"]);
+    assert!(found.is_empty());
+
+    let (variables, found) = differences("T", "p");
+    assert_eq!(variables, vec!["p"]);
+    assert_eq!(found, vec![difference(&[false], true)]);
+}
+
+fn argument(premises: &[&str], conclusion: &str) -> Argument
+{
+    let mut sources = premises.to_vec();
+    sources.push(conclusion);
+
+    let mut formulas = formula::parse_shared(&sources).unwrap();
+    let conclusion = formulas.pop().unwrap();
+    let ordering = RowOrdering { order: table::RowOrder::Binary, reversed: false };
+
+    analysis::check_argument(&formulas, &conclusion, ordering, 1).unwrap()
+}
+
+#[test]
+fn valid_arguments()
+{
+    use RowKind::{Critical, NotCritical};
+
+    // Modus ponens over the rows pq = FF, FT, TF, TT
+    let modus_ponens = argument(&["p => q", "p"], "q");
+    assert!(modus_ponens.is_valid());
+    assert_eq!(modus_ponens.rows, vec![NotCritical, NotCritical, NotCritical, Critical]);
+    assert_eq!(modus_ponens.tables.len(), 3);
+
+    // Without premises every row is critical, the argument is valid when the conclusion is a tautology
+    let excluded_middle = argument(&[], "p | !p");
+    assert!(excluded_middle.is_valid());
+    assert_eq!(excluded_middle.rows, vec![Critical, Critical]);
+
+    // Inconsistent premises have no critical row and entail anything
+    let explosion = argument(&["p", "!p"], "q");
+    assert!(explosion.is_valid());
+    assert!(explosion.rows.iter().all(|kind| *kind == NotCritical));
+}
+
+#[test]
+fn invalid_arguments()
+{
+    use RowKind::{Counterexample, Critical, NotCritical};
+
+    // Affirming the consequent fails where p is false and q true
+    let fallacy = argument(&["p => q", "q"], "p");
+    assert!(!fallacy.is_valid());
+    assert_eq!(fallacy.rows, vec![NotCritical, Counterexample, NotCritical, Critical]);
+
+    // The conclusion introduces a variable no premise constrains
+    let unrelated = argument(&["p"], "q");
+    assert!(!unrelated.is_valid());
+    assert_eq!(unrelated.rows, vec![NotCritical, NotCritical, Counterexample, Critical]);
+
+    let contradiction = argument(&[], "p & !p");
+    assert_eq!(contradiction.rows, vec![Counterexample, Counterexample]);
+}
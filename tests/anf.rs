@@ -0,0 +1,60 @@
+//! Algebraic normal forms must evaluate back to the table they were computed from
+
+use ttbl::anf;
+use ttbl::formula::Formula;
+use ttbl::table;
+
+mod common;
+use common::{random_formula, Rng};
+
+#[test]
+fn polynomials_evaluate_to_the_table()
+{
+    let mut rng = Rng(0x0a1f_ab3d_7e11_c0de);
+
+    for _ in 0..300
+    {
+        let source = random_formula(&mut rng, 4);
+        let formula = Formula::parse(&source).unwrap();
+        let truth = table::truth_vector(&formula.groups, formula.variables.len() as u32, 1).unwrap();
+        let coefficients = anf::mobius_transform(&truth);
+
+        // A row is the XOR of the terms whose variables are all true in it
+        let evaluated = (0..truth.len())
+            .map(|row| (0..truth.len()).filter(|term| coefficients[*term] && term & row == *term).count() % 2 == 1)
+            .collect::<Vec<bool>>();
+
+        assert_eq!(evaluated, truth, "{}", source);
+        assert_eq!(anf::mobius_transform(&coefficients), truth, "{}", source);
+    }
+}
+
+#[test]
+fn renders_with_xor_and_and()
+{
+    let variables = vec![String::from("a"), String::from("b"), String::from("c")];
+    let render = |source: &str| {
+        let formula = Formula::parse_with_variables(source, variables.clone()).unwrap();
+        let truth = table::truth_vector(&formula.groups, 3, 1).unwrap();
+        let coefficients = anf::mobius_transform(&truth);
+        (anf::render(&coefficients, &variables), anf::degree(&coefficients))
+    };
+
+    assert_eq!(render("a => b"), (String::from("1 ^ a ^ a&b"), 2));
+    assert_eq!(render("(a <=> b) <=> c"), (String::from("a ^ b ^ c"), 1));
+    assert_eq!(render("(a & b) | (a & c) | (b & c)"), (String::from("a&b ^ a&c ^ b&c"), 2));
+    assert_eq!(render("a & !a"), (String::from("0"), 0));
+    assert_eq!(render("a | !a"), (String::from("1"), 0));
+}
+
+#[test]
+fn constant_polynomials()
+{
+    // Without variables the table has one row and the polynomial is a constant
+    assert_eq!(anf::mobius_transform(&[true]), vec![true]);
+    assert_eq!(anf::mobius_transform(&[false]), vec![false]);
+    assert_eq!(anf::render(&[true], &[]), "1");
+    assert_eq!(anf::render(&[false], &[]), "0");
+    assert_eq!(anf::degree(&[true]), 0);
+    assert_eq!(anf::degree(&[false]), 0);
+}
@@ -0,0 +1,184 @@
+//! BDDs must agree with the truth table, be canonical, and keep their function across reordering
+
+use ttbl::analysis;
+use ttbl::bdd::{self, Bdd, NodeId};
+use ttbl::formula::{self, Formula};
+use ttbl::table;
+
+mod common;
+use common::{random_formula, Rng};
+
+/// Truth vector of a BDD over its variables, first variable as the most significant bit
+fn bdd_truth(manager: &mut Bdd, root: NodeId) -> Vec<bool>
+{
+    let size = manager.var_count();
+
+    (0..1u32 << size)
+        .map(|minterm| {
+            let mut node = root;
+            for loc in 0..size
+            {
+                node = manager.restrict(node, loc, minterm >> (size - loc - 1) & 1 == 1).unwrap();
+            }
+            node == bdd::TRUE
+        })
+        .collect()
+}
+
+#[test]
+fn matches_truth_tables()
+{
+    let mut rng = Rng(0x0b00_1ead_d1a9_7a11);
+
+    for _ in 0..300
+    {
+        let source = random_formula(&mut rng, 4);
+        let formula = Formula::parse(&source).unwrap();
+        let size = formula.variables.len();
+
+        let mut manager = Bdd::with_natural_order(size, bdd::DEFAULT_NODE_LIMIT);
+        let root = manager.from_node(&formula.root).unwrap();
+        let truth = table::truth_vector(&formula.groups, size as u32, 1).unwrap();
+
+        assert_eq!(bdd_truth(&mut manager, root), truth, "{}", source);
+        assert_eq!(manager.count_models(root), analysis::count_models(&formula, 1).unwrap() as u128, "{}", source);
+
+        match manager.find_model(root)
+        {
+            Some(model) => {
+                let minterm = model.iter().fold(0, |acc, val| acc << 1 | *val as usize);
+                assert!(truth[minterm], "{}", source);
+            },
+            None => assert!(truth.iter().all(|val| !val), "{}", source),
+        }
+    }
+}
+
+#[test]
+fn equivalent_formulas_share_a_node()
+{
+    let formulas = formula::parse_shared(&["(a => b) & (b => c)", "!(a & !b) & (!b | c)", "a => c"]).unwrap();
+    let mut manager = Bdd::with_natural_order(formulas[0].variables.len(), bdd::DEFAULT_NODE_LIMIT);
+    let roots = formulas.iter().map(|formula| manager.from_node(&formula.root).unwrap()).collect::<Vec<NodeId>>();
+
+    assert_eq!(roots[0], roots[1]);
+    assert_ne!(roots[0], roots[2]);
+}
+
+#[test]
+fn restricts_variables()
+{
+    let formula = Formula::parse("(a & b) | (!a & c)").unwrap();
+    let mut manager = Bdd::with_natural_order(3, bdd::DEFAULT_NODE_LIMIT);
+    let root = manager.from_node(&formula.root).unwrap();
+
+    let b = manager.variable(1).unwrap();
+    let c = manager.variable(2).unwrap();
+
+    assert_eq!(manager.restrict(root, 0, true).unwrap(), b);
+    assert_eq!(manager.restrict(root, 0, false).unwrap(), c);
+}
+
+#[test]
+fn sifting_finds_the_interleaved_order()
+{
+    // The textbook example: exponential with all a's before the b's, linear when interleaved
+    let formula = Formula::parse("(a1 & b1) | (a2 & b2) | (a3 & b3) | (a4 & b4)").unwrap();
+    let separated = vec![0, 2, 4, 6, 1, 3, 5, 7];
+
+    let mut manager = Bdd::new(separated, bdd::DEFAULT_NODE_LIMIT);
+    let root = manager.from_node(&formula.root).unwrap();
+    assert_eq!(manager.size(root), 32);
+
+    let (mut sifted, sifted_root) = manager.sift(root).unwrap();
+    assert_eq!(sifted.size(sifted_root), 10);
+    assert_eq!(sifted.count_models(sifted_root), manager.count_models(root));
+
+    let mut natural = Bdd::with_natural_order(8, bdd::DEFAULT_NODE_LIMIT);
+    let natural_root = natural.from_node(&formula.root).unwrap();
+    assert_eq!(bdd_truth(&mut sifted, sifted_root), bdd_truth(&mut natural, natural_root));
+}
+
+#[test]
+fn sifting_keeps_the_function()
+{
+    let mut rng = Rng(0x5177_ed00_0bdd_0001);
+
+    for _ in 0..200
+    {
+        let source = random_formula(&mut rng, 5);
+        let formula = Formula::parse(&source).unwrap();
+        let size = formula.variables.len();
+
+        // Start from the reversed order so there is something to move
+        let mut manager = Bdd::new((0..size).rev().collect(), bdd::DEFAULT_NODE_LIMIT);
+        let root = manager.from_node(&formula.root).unwrap();
+        let (mut sifted, sifted_root) = manager.sift(root).unwrap();
+
+        assert!(sifted.size(sifted_root) <= manager.size(root), "{}", source);
+
+        let mut natural = Bdd::with_natural_order(size, bdd::DEFAULT_NODE_LIMIT);
+        let natural_root = natural.from_node(&formula.root).unwrap();
+        assert_eq!(bdd_truth(&mut sifted, sifted_root), bdd_truth(&mut natural, natural_root), "{}", source);
+    }
+}
+
+#[test]
+fn sifts_wide_separated_orders()
+{
+    // Ten pairs, 2^11 nodes with the a's before the b's and two per pair once interleaved
+    let pairs = 10;
+    let source = (1..=pairs).map(|pair| format!("(a{} & b{})", pair, pair)).collect::<Vec<String>>().join(" | ");
+    let formula = Formula::parse(&source).unwrap();
+    let separated = (0..pairs).map(|pair| 2 * pair).chain((0..pairs).map(|pair| 2 * pair + 1)).collect();
+
+    let mut manager = Bdd::new(separated, bdd::DEFAULT_NODE_LIMIT);
+    let root = manager.from_node(&formula.root).unwrap();
+    assert_eq!(manager.size(root), 1 << (pairs + 1));
+
+    let (sifted, sifted_root) = manager.sift(root).unwrap();
+    assert_eq!(sifted.size(sifted_root), 2 * pairs + 2);
+    assert_eq!(sifted.count_models(sifted_root), manager.count_models(root));
+}
+
+#[test]
+fn respects_the_node_limit()
+{
+    let formula = Formula::parse("(a1 & b1) | (a2 & b2) | (a3 & b3) | (a4 & b4)").unwrap();
+    let mut manager = Bdd::new(vec![0, 2, 4, 6, 1, 3, 5, 7], 20);
+
+    assert_eq!(manager.from_node(&formula.root), Err(bdd::BddError::TooLarge { limit: 20 }));
+}
+
+#[test]
+fn exports_dot()
+{
+    let formula = Formula::parse("a & b").unwrap();
+    let mut manager = Bdd::with_natural_order(2, bdd::DEFAULT_NODE_LIMIT);
+    let root = manager.from_node(&formula.root).unwrap();
+    let dot = manager.to_dot(root, &formula.variables);
+
+    assert!(dot.starts_with("digraph bdd {"));
+    assert!(dot.contains("[label=\"a\", shape=circle];"));
+    assert!(dot.contains("[label=\"b\", shape=circle];"));
+    assert!(dot.contains(" -> n0 [style=dashed];"));
+    assert!(dot.contains(" -> n1;"));
+}
+
+#[test]
+fn constants_without_variables()
+{
+    let mut manager = Bdd::with_natural_order(0, bdd::DEFAULT_NODE_LIMIT);
+
+    let truth = manager.from_node(&Formula::parse("T & !F").unwrap().root).unwrap();
+    assert_eq!(truth, bdd::TRUE);
+    assert_eq!((manager.count_models(truth), manager.find_model(truth)), (1, Some(vec![])));
+
+    let falsity = manager.from_node(&Formula::parse("F | (T => F)").unwrap().root).unwrap();
+    assert_eq!(falsity, bdd::FALSE);
+    assert_eq!((manager.count_models(falsity), manager.find_model(falsity)), (0, None));
+
+    let (sifted, root) = manager.sift(truth).unwrap();
+    assert_eq!((sifted.size(root), sifted.order().len()), (1, 0));
+    assert_eq!(manager.variable(0), Err(bdd::BddError::UnknownVariable { location: 0 }));
+}
@@ -0,0 +1,94 @@
+//! The lowering must fold constants, share common subexpressions and cancel double negations
+//! without changing any result
+
+use ttbl::bytecode::{self, Instruction, Operand, Program};
+use ttbl::formula::Formula;
+use ttbl::scanner::OperatorType;
+use ttbl::table;
+
+fn compile(source: &str) -> (Formula, Program)
+{
+    let formula = Formula::parse(source).unwrap();
+    let program = bytecode::compile(&formula.groups).unwrap();
+    (formula, program)
+}
+
+fn result(program: &Program) -> Operand
+{
+    *program.outputs.last().unwrap()
+}
+
+/// Values of the whole formula for every row, in minterm order
+fn evaluate(program: &Program, size: u32) -> Vec<bool>
+{
+    let mut registers = vec![false; program.register_count];
+    let mut out_eval = vec![false; program.outputs.len()];
+
+    (0..1u32 << size)
+        .map(|minterm| {
+            program.evaluate(&table::minterm_values(size, minterm), &mut registers, &mut out_eval);
+            *out_eval.last().unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn idempotent_operations_reuse_the_operand()
+{
+    for source in ["p & p", "p | p", "(p) & p"]
+    {
+        let (_, program) = compile(source);
+        assert!(program.instructions.is_empty(), "{}", source);
+        assert_eq!(result(&program), Operand::Input(0), "{}", source);
+    }
+
+    // A variable is always equivalent to itself
+    let (_, program) = compile("p <=> p");
+    assert!(program.instructions.is_empty());
+    assert_eq!(result(&program), Operand::Const(true));
+}
+
+#[test]
+fn double_negations_cancel()
+{
+    let (_, program) = compile("!!p");
+    assert!(program.instructions.is_empty());
+    assert_eq!(result(&program), Operand::Input(0));
+
+    // Only one negation is left, the other two cancel
+    let (formula, program) = compile("!p & !!!p");
+    assert_eq!(program.instructions, vec![Instruction::Not { dst: 0, src: Operand::Input(0) }]);
+    assert_eq!(evaluate(&program, formula.variables.len() as u32), vec![true, false]);
+}
+
+#[test]
+fn constant_subtrees_fold()
+{
+    for (source, expected) in [("T & !F", true), ("(T => F) | (F <=> F) & F", false), ("!(T | q) & q", false), ("q | !q & F | T", true)]
+    {
+        let (_, program) = compile(source);
+        assert!(program.instructions.is_empty(), "{}", source);
+        assert_eq!(result(&program), Operand::Const(expected), "{}", source);
+    }
+
+    // Only the part that depends on a variable is left
+    let (formula, program) = compile("(p & T) | (F & q) | (p => F)");
+    assert_eq!(program.instructions.len(), 2);
+    assert_eq!(evaluate(&program, formula.variables.len() as u32), vec![true, true, true, true]);
+}
+
+#[test]
+fn common_subexpressions_share_registers()
+{
+    // `p & q` and `q & p` are the same operation, so the OR of the two is that operation
+    let (_, program) = compile("(p & q) | (q & p)");
+    assert_eq!(program.instructions, vec![Instruction::Binary { op: OperatorType::AND, dst: 0, left: Operand::Input(0), right: Operand::Input(1) }]);
+
+    let (formula, program) = compile("(p & q) | !(q & p)");
+    assert_eq!(program.instructions.len(), 3);
+    assert_eq!(evaluate(&program, formula.variables.len() as u32), vec![true; 4]);
+
+    let (formula, program) = compile("((p => q) & r) | ((p => q) & !r)");
+    assert_eq!(program.instructions.len(), 5);
+    assert_eq!(evaluate(&program, formula.variables.len() as u32), table::truth_vector(&formula.groups, 3, 1).unwrap());
+}
@@ -0,0 +1,64 @@
+//! Canonical forms must parse back into formulas equivalent to the original
+
+use ttbl::canonical;
+use ttbl::formula::{self, Formula};
+use ttbl::sat;
+use ttbl::table;
+
+mod common;
+use common::{random_formula, Rng};
+
+fn assert_equivalent(source: &str, rendered: &str)
+{
+    let formulas = formula::parse_shared(&[source, rendered]).expect(rendered);
+    assert_eq!(sat::find_difference(&formulas[0], &formulas[1]), None, "{} vs {}", source, rendered);
+}
+
+#[test]
+fn canonical_forms_round_trip()
+{
+    let mut rng = Rng(0x5eed_5eed_5eed_5eed);
+
+    for _ in 0..300
+    {
+        let source = random_formula(&mut rng, 4);
+        let formula = Formula::parse(&source).unwrap();
+        let truth = table::truth_vector(&formula.groups, formula.variables.len() as u32, 1).unwrap();
+
+        assert_equivalent(&source, &canonical::canonical_dnf(&formula.variables, &truth));
+        assert_equivalent(&source, &canonical::canonical_cnf(&formula.variables, &truth));
+    }
+}
+
+#[test]
+fn index_notation()
+{
+    let formula = Formula::parse("p => q").unwrap();
+    let truth = table::truth_vector(&formula.groups, 2, 1).unwrap();
+
+    assert_eq!(canonical::minterm_notation(&truth), "Σm(0, 1, 3)");
+    assert_eq!(canonical::maxterm_notation(&truth), "ΠM(2)");
+    assert_eq!(canonical::canonical_dnf(&formula.variables, &truth), "(!p & !q) | (!p & q) | (p & q)");
+    assert_eq!(canonical::canonical_cnf(&formula.variables, &truth), "(!p | q)");
+}
+
+#[test]
+fn constants_render_as_literals()
+{
+    // No variables: a single row
+    assert_eq!(canonical::canonical_dnf(&[], &[true]), "TRUE");
+    assert_eq!(canonical::canonical_dnf(&[], &[false]), "FALSE");
+    assert_eq!(canonical::canonical_cnf(&[], &[true]), "TRUE");
+    assert_eq!(canonical::canonical_cnf(&[], &[false]), "FALSE");
+
+    let variables = vec![String::from("p"), String::from("q")];
+    assert_eq!(canonical::canonical_dnf(&variables, &[false; 4]), "FALSE");
+    assert_eq!(canonical::canonical_cnf(&variables, &[true; 4]), "TRUE");
+    assert_eq!(canonical::minterm_notation(&[false; 4]), "Σm()");
+    assert_eq!(canonical::maxterm_notation(&[true; 4]), "ΠM()");
+
+    for rendered in ["TRUE", "FALSE"]
+    {
+        assert!(Formula::parse(rendered).unwrap().variables.is_empty());
+    }
+}
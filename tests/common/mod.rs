@@ -0,0 +1,47 @@
+//! Helpers shared by the integration tests
+
+#![allow(dead_code)]
+
+/// Small deterministic xorshift generator so failures are reproducible
+pub struct Rng(pub u64);
+
+impl Rng
+{
+    pub fn next(&mut self) -> u64
+    {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize
+    {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+pub fn random_formula(rng: &mut Rng, depth: usize) -> String
+{
+    const VARIABLES: [&str; 5] = ["a", "b", "c", "d", "e"];
+    const OPERATORS: [&str; 4] = ["&", "|", "=>", "<=>"];
+
+    if depth == 0 || rng.below(4) == 0
+    {
+        return match rng.below(12)
+        {
+            0 => String::from("TRUE"),
+            1 => String::from("FALSE"),
+            _ => String::from(VARIABLES[rng.below(VARIABLES.len())]),
+        };
+    }
+
+    if rng.below(5) == 0
+    {
+        return format!("!({})", random_formula(rng, depth - 1));
+    }
+
+    let left = random_formula(rng, depth - 1);
+    let right = random_formula(rng, depth - 1);
+    format!("({} {} {})", left, OPERATORS[rng.below(OPERATORS.len())], right)
+}
@@ -0,0 +1,93 @@
+//! Vacuous variables found on the table must match the BDD and be safe to drop
+
+use ttbl::analysis;
+use ttbl::bdd::{self, Bdd};
+use ttbl::execution;
+use ttbl::formula::Formula;
+use ttbl::table::{self, RowOrder, RowOrdering};
+
+mod common;
+use common::{random_formula, Rng};
+
+#[test]
+fn finds_vacuous_variables()
+{
+    let formula = Formula::parse("{q & !q} | p | r").unwrap();
+    let ordering = RowOrdering { order: RowOrder::Gray, reversed: true };
+    let table = table::evaluate_table(&formula.groups, 3, ordering, 1).unwrap();
+
+    assert_eq!(analysis::dependencies(&table), vec![vec![false, false, false], vec![false, true, true]]);
+}
+
+#[test]
+fn table_and_bdd_agree()
+{
+    let mut rng = Rng(0x0dea_d0ff_5e7f_ace5);
+
+    for _ in 0..300
+    {
+        let source = random_formula(&mut rng, 4);
+        let formula = Formula::parse(&source).unwrap();
+        let size = formula.variables.len();
+
+        let table = table::evaluate_table(&formula.groups, size as u32, RowOrdering::default(), 1).unwrap();
+        let depends = analysis::dependencies(&table);
+
+        let mut manager = Bdd::with_natural_order(size, bdd::DEFAULT_NODE_LIMIT);
+        let nodes = execution::groups_to_nodes(&formula.groups).unwrap();
+        let supports = nodes.iter().map(|node| {
+            let root = manager.from_node(node).unwrap();
+            manager.support(root)
+        });
+
+        assert!(depends.into_iter().eq(supports), "{}", source);
+    }
+}
+
+#[test]
+fn dropping_keeps_every_column()
+{
+    let mut rng = Rng(0x0c0f_fee0_ba5e_ba11);
+
+    for _ in 0..300
+    {
+        let source = random_formula(&mut rng, 4);
+        let formula = Formula::parse(&source).unwrap();
+        let size = formula.variables.len();
+
+        let table = table::evaluate_table(&formula.groups, size as u32, RowOrdering::default(), 1).unwrap();
+        let depends = analysis::dependencies(&table);
+        let unused = (0..size).map(|loc| depends.iter().all(|col| !col[loc])).collect::<Vec<bool>>();
+
+        let reduced = formula.without_variables(&unused);
+        let reduced_table = table::evaluate_table(&reduced.groups, reduced.variables.len() as u32, RowOrdering::default(), 1).unwrap();
+
+        assert_eq!(reduced.variables.len(), unused.iter().filter(|unused| !**unused).count());
+        assert_eq!(reduced_table.outputs.len() << (size - reduced.variables.len()), table.outputs.len());
+
+        // Every row of the full table matches the reduced row with the kept variables
+        for (inputs, outputs) in table.inputs.iter().zip(table.outputs.iter())
+        {
+            let kept = (0..size).filter(|loc| !unused[*loc]).map(|loc| inputs[loc]).collect::<Vec<bool>>();
+            let row = reduced_table.inputs.iter().position(|row| *row == kept).unwrap();
+            assert_eq!(&reduced_table.outputs[row], outputs, "{}", source);
+        }
+    }
+}
+
+#[test]
+fn constant_columns_depend_on_nothing()
+{
+    let formula = Formula::parse("T").unwrap();
+    let table = table::evaluate_table(&formula.groups, 0, RowOrdering::default(), 1).unwrap();
+    assert_eq!(analysis::dependencies(&table), vec![Vec::<bool>::new()]);
+
+    let formula = Formula::parse("{a & !a} | {b | !b}").unwrap();
+    let table = table::evaluate_table(&formula.groups, 2, RowOrdering::default(), 1).unwrap();
+    assert_eq!(analysis::dependencies(&table), vec![vec![false; 2], vec![false; 2], vec![false; 2]]);
+
+    // Dropping every variable leaves the constant
+    let reduced = formula.without_variables(&[true, true]);
+    assert!(reduced.variables.is_empty());
+    assert_eq!(table::truth_vector(&reduced.groups, 0, 1).unwrap(), vec![true]);
+}
@@ -0,0 +1,156 @@
+//! Heuristic covers must be exact, close to minimal on small functions and work without a table
+
+use ttbl::espresso::{self, OffSet};
+use ttbl::execution;
+use ttbl::formula::Formula;
+use ttbl::minimize::{self, Implicant};
+use ttbl::sat;
+use ttbl::table::{self, RowOrder, RowOrdering};
+
+mod common;
+use common::{random_formula, Rng};
+
+fn truth_vectors(formula: &Formula) -> Vec<Vec<bool>>
+{
+    let ordering = RowOrdering { order: RowOrder::Binary, reversed: false };
+    let table = table::evaluate_table(&formula.groups, formula.variables.len() as u32, ordering, 1).unwrap();
+
+    (0..formula.groups.len())
+        .map(|output| table.outputs.iter().map(|row| row[output]).collect())
+        .collect()
+}
+
+#[test]
+fn covers_match_every_output()
+{
+    let mut rng = Rng(0x0e5b_7e55_0000_0041);
+
+    for _ in 0..200
+    {
+        let source = format!("{{{}}} | {{{}}}", random_formula(&mut rng, 3), random_formula(&mut rng, 3));
+        let formula = Formula::parse(&source).unwrap();
+        let size = formula.variables.len() as u32;
+        let truths = truth_vectors(&formula);
+
+        let cover = espresso::minimize(size, truths.len(), espresso::from_truth_vectors(&truths, &[]));
+
+        for (output, truth) in truths.iter().enumerate()
+        {
+            let products = espresso::output_products(&cover, size, output);
+            let cubes = products.iter().map(|term| Implicant::from_term(size, term)).collect::<Vec<Implicant>>();
+
+            for minterm in 0..truth.len() as u32
+            {
+                assert_eq!(cubes.iter().any(|cube| cube.covers(minterm)), truth[minterm as usize], "{} output {}", source, output);
+            }
+        }
+    }
+}
+
+#[test]
+fn close_to_minimal()
+{
+    // Never more than one product over the minimum, for every function of three variables and a
+    // sample of functions of five
+    let mut functions = (0..256u64).map(|function| (3, function)).collect::<Vec<(u32, u64)>>();
+    let mut rng = Rng(0x00c1_05e0_0000_0041);
+    functions.extend((0..300).map(|_| (5, rng.next())));
+
+    for (size, function) in functions
+    {
+        let truth = (0..1u32 << size).map(|minterm| function >> minterm & 1 == 1).collect::<Vec<bool>>();
+        let exact = minimize::minimal_sum_of_products(size, &truth, &[], minimize::DEFAULT_COVER_LIMIT).unwrap().len();
+
+        let heuristic = espresso::minimize(size, 1, espresso::from_truth_vectors(&[truth], &[])).len();
+
+        assert!(heuristic >= exact && heuristic <= exact + 1, "function {:x}: {} vs {}", function, heuristic, exact);
+    }
+}
+
+#[test]
+fn minimizes_without_a_table()
+{
+    let source = (1..=12).map(|index| format!("(a{0} & b{0} & !c) | (a{0} & b{0} & c)", index)).collect::<Vec<String>>().join(" | ");
+    let formula = Formula::parse(&source).unwrap();
+    let size = formula.variables.len() as u32;
+    assert!(size > 16);
+
+    let nodes = execution::groups_to_nodes(&formula.groups).unwrap();
+    let spec = espresso::from_nodes(size, &nodes, vec![], 10_000).unwrap();
+    assert!(matches!(spec.off, OffSet::Complement(_)));
+
+    let cover = espresso::minimize(size, 1, spec);
+    assert_eq!(cover.len(), 12);
+
+    let sop = minimize::sum_of_products_node(&espresso::output_products(&cover, size, 0));
+    assert_eq!(sat::find_node_difference(&nodes[0], &sop, size as usize), None);
+}
+
+#[test]
+fn dont_cares_are_free()
+{
+    let mut rng = Rng(0x0dc0_0dc0_0000_0042);
+
+    for _ in 0..200
+    {
+        let formula = Formula::parse(&random_formula(&mut rng, 3)).unwrap();
+        let size = formula.variables.len() as u32;
+        let truth = truth_vectors(&formula).pop().unwrap();
+        let dont_care = (0..truth.len()).map(|_| rng.below(4) == 0).collect::<Vec<bool>>();
+
+        let cover = espresso::minimize(size, 1, espresso::from_truth_vectors(std::slice::from_ref(&truth), std::slice::from_ref(&dont_care)));
+        let cubes = cover.iter().map(|cube| cube.inputs).collect::<Vec<Implicant>>();
+
+        for minterm in 0..truth.len() as u32
+        {
+            if !dont_care[minterm as usize]
+            {
+                assert_eq!(cubes.iter().any(|cube| cube.covers(minterm)), truth[minterm as usize]);
+            }
+        }
+
+        let exact = minimize::minimal_sum_of_products(size, &truth, &dont_care, minimize::DEFAULT_COVER_LIMIT).unwrap();
+        assert!(cover.len() <= exact.len() + 1);
+    }
+}
+
+#[test]
+fn dont_care_sets_become_block_cubes()
+{
+    // Everything is a don't-care at 16 variables, a single cube covers the set
+    let size = 16;
+    let truth = (0..1u32 << size).map(|minterm| minterm % 3 == 0).collect::<Vec<bool>>();
+    let spec = espresso::from_truth_vectors(std::slice::from_ref(&truth), &[vec![true; 1 << size]]);
+    assert_eq!(spec.dont_care, vec![vec![Implicant { value: 0, mask: 0xffff }]]);
+    assert!(espresso::minimize(size, 1, spec).len() <= 1);
+
+    // Halves with the same rows merge on the first variable: `b` over a, b, c
+    let dont_care = vec![false, false, true, true, false, false, true, true];
+    let spec = espresso::from_truth_vectors(&[vec![false; 8]], &[dont_care]);
+    assert_eq!(spec.dont_care, vec![vec![Implicant { value: 0b010, mask: 0b101 }]]);
+
+    // An empty vector stands for no don't-cares, as for an output without a clause
+    let spec = espresso::from_truth_vectors(&[vec![false, true], vec![true, true]], &[vec![], vec![]]);
+    assert_eq!(spec.dont_care, vec![vec![], vec![]]);
+    let cover = espresso::minimize(1, 2, spec);
+    assert_eq!(espresso::output_products(&cover, 1, 0), vec![vec![(0, true)]]);
+    assert_eq!(espresso::output_products(&cover, 1, 1), vec![vec![]]);
+}
+
+#[test]
+fn constant_outputs()
+{
+    // No variables, one row per output
+    let cover = espresso::minimize(0, 2, espresso::from_truth_vectors(&[vec![true], vec![false]], &[]));
+    assert_eq!(espresso::output_products(&cover, 0, 0), vec![vec![]]);
+    assert!(espresso::output_products(&cover, 0, 1).is_empty());
+
+    // Constant columns next to one that is not
+    let formula = Formula::parse("{a & !a} | {b | !b} | a").unwrap();
+    let truths = truth_vectors(&formula);
+    assert_eq!(truths.len(), 3);
+    let cover = espresso::minimize(2, truths.len(), espresso::from_truth_vectors(&truths, &[]));
+    assert!(espresso::output_products(&cover, 2, 0).is_empty());
+    assert_eq!(espresso::output_products(&cover, 2, 1), vec![vec![]]);
+    assert_eq!(espresso::output_products(&cover, 2, 2), vec![vec![]]);
+}
@@ -0,0 +1,106 @@
+//! Rewritten circuits must only use the basis and agree with the formula on every row
+
+use ttbl::formula::Formula;
+use ttbl::gates::{self, GateError};
+use ttbl::post::{self, Connective};
+use ttbl::table;
+
+mod common;
+use common::{random_formula, Rng};
+
+#[test]
+fn circuits_match_tables()
+{
+    let mut rng = Rng(0x0ba5_15ba_5150_a7e5);
+
+    for basis in ["nand", "nor", "{&, !}", "{|, !}", "{&, ^, 1}", "{=>, 0}"]
+    {
+        let connectives = post::parse_connectives(basis).unwrap();
+        let symbols = connectives.iter().map(Connective::symbol).collect::<Vec<&str>>();
+
+        for _ in 0..100
+        {
+            let source = random_formula(&mut rng, 4);
+            let formula = Formula::parse(&source).unwrap();
+            let size = formula.variables.len() as u32;
+
+            // Without a variable to build them from, only bases with constants can express constants
+            if size == 0 { continue; }
+
+            let (circuit, root) = gates::rewrite(&formula.root, &connectives).unwrap();
+            let truth = table::truth_vector(&formula.groups, size, 1).unwrap();
+
+            for (minterm, expected) in truth.iter().enumerate()
+            {
+                let values = table::minterm_values(size, minterm as u32);
+                assert_eq!(circuit.evaluate(root, &values), *expected, "{} in {}", source, basis);
+            }
+
+            assert!(circuit.gate_counts(root).keys().all(|symbol| symbols.contains(symbol)), "{} in {}", source, basis);
+        }
+    }
+}
+
+#[test]
+fn counts_shared_gates_once()
+{
+    let rewrite = |basis: &str, source: &str| {
+        let formula = Formula::parse(source).unwrap();
+        let (circuit, root) = gates::rewrite(&formula.root, &post::parse_connectives(basis).unwrap()).unwrap();
+        (circuit.render(root, &formula.variables), circuit.gate_counts(root).values().sum::<usize>())
+    };
+
+    assert_eq!(rewrite("nand", "a | b"), (String::from("((a nand a) nand (b nand b))"), 3));
+    assert_eq!(rewrite("nand", "(a & b) | (!a & c)"), (String::from("((a nand b) nand ((a nand a) nand c))"), 4));
+    assert_eq!(rewrite("nor", "a & !b"), (String::from("((a nor a) nor b)"), 2));
+    assert_eq!(rewrite("nand", "!!a & T"), (String::from("a"), 0));
+}
+
+#[test]
+fn reports_what_the_basis_cannot_build()
+{
+    let formula = Formula::parse("a => b").unwrap();
+    let monotone = post::parse_connectives("{&, |}").unwrap();
+    assert_eq!(gates::rewrite(&formula.root, &monotone).err(), Some(GateError::Inexpressible(Connective::Implies)));
+
+    // AND of copies of an input is never constant
+    let constant = Formula::parse("T").unwrap();
+    let and = post::parse_connectives("{&}").unwrap();
+    assert_eq!(gates::rewrite(&constant.root, &and).err(), Some(GateError::Inexpressible(Connective::True)));
+}
+
+#[test]
+fn builds_constants_from_a_fresh_input()
+{
+    let nand = post::parse_connectives("nand").unwrap();
+    let fresh = [String::from("x")];
+
+    for (source, expected) in [("T", true), ("!T & (F | T)", false)]
+    {
+        let formula = Formula::parse(source).unwrap();
+        assert!(formula.variables.is_empty());
+
+        let (circuit, root) = gates::rewrite(&formula.root, &nand).unwrap();
+        assert!(circuit.render(root, &fresh).contains('x'), "{}", source);
+        assert_eq!(circuit.evaluate(root, &[false]), expected, "{}", source);
+        assert_eq!(circuit.evaluate(root, &[true]), expected, "{}", source);
+    }
+
+    // A basis with the constant needs no input
+    let (circuit, root) = gates::rewrite(&Formula::parse("T").unwrap().root, &post::parse_connectives("{&, 1}").unwrap()).unwrap();
+    assert_eq!(circuit.render(root, &[]), "1");
+}
+
+#[test]
+fn rejects_malformed_bases()
+{
+    for source in ["", "{}", "{ }"]
+    {
+        assert_eq!(post::parse_connectives(source), Err(post::ConnectiveError::Empty), "{:?}", source);
+    }
+
+    for (source, unknown) in [("and2", "and2"), ("{&, ||}", "||"), ("{&, nand, 2}", "2")]
+    {
+        assert_eq!(post::parse_connectives(source), Err(post::ConnectiveError::Unknown(String::from(unknown))), "{}", source);
+    }
+}
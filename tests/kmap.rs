@@ -0,0 +1,44 @@
+//! Karnaugh map layouts must place every minterm once with neighbouring cells one variable apart
+
+use ttbl::kmap::{self, KarnaughMap};
+
+#[test]
+fn neighbours_differ_in_one_variable()
+{
+    for size in 0..=kmap::MAX_VARIABLES
+    {
+        let map = KarnaughMap::new(size);
+        let (height, width) = (map.rows.len(), map.columns.len());
+
+        let mut seen = vec![false; 1 << size];
+        for row in 0..height
+        {
+            for column in 0..width
+            {
+                let minterm = map.minterm(row, column);
+                assert!(!seen[minterm as usize], "{} variables: minterm {} placed twice", size, minterm);
+                seen[minterm as usize] = true;
+
+                // Wrapping around the edges, maps of a single row or column have no vertical or horizontal neighbours
+                let right = map.minterm(row, (column + 1) % width);
+                let below = map.minterm((row + 1) % height, column);
+                assert!(width < 2 || (minterm ^ right).count_ones() == 1, "{} variables at {}, {}", size, row, column);
+                assert!(height < 2 || (minterm ^ below).count_ones() == 1, "{} variables at {}, {}", size, row, column);
+            }
+        }
+
+        assert!(seen.iter().all(|seen| *seen));
+    }
+}
+
+#[test]
+fn labels()
+{
+    assert_eq!(kmap::gray_codes(2), vec![0, 1, 3, 2]);
+    assert_eq!(kmap::gray_codes(3).iter().map(|code| kmap::label(*code, 3)).collect::<Vec<String>>(),
+        vec!["000", "001", "011", "010", "110", "111", "101", "100"]);
+
+    let map = KarnaughMap::new(3);
+    assert_eq!((map.row_bits, map.column_bits), (1, 2));
+    assert_eq!(map.minterm(1, 2), 0b111);
+}
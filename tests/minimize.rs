@@ -0,0 +1,135 @@
+//! Minimal sums of products must be equivalent to the original and no larger than any other cover
+
+use ttbl::canonical;
+use ttbl::execution;
+use ttbl::formula::{self, DontCares, Formula};
+use ttbl::minimize::{self, Implicant};
+use ttbl::sat;
+use ttbl::table;
+
+mod common;
+use common::{random_formula, Rng};
+
+#[test]
+fn minimal_forms_are_equivalent()
+{
+    let mut rng = Rng(0x0dd_ba11_5eed_f00d);
+
+    for _ in 0..300
+    {
+        let source = random_formula(&mut rng, 4);
+        let formula = Formula::parse(&source).unwrap();
+        let size = formula.variables.len() as u32;
+        let truth = table::truth_vector(&formula.groups, size, 1).unwrap();
+
+        let products = minimize::minimal_sum_of_products(size, &truth, &[], minimize::DEFAULT_COVER_LIMIT).unwrap();
+        let rendered = canonical::render_sum_of_products(&formula.variables, &products);
+
+        let formulas = formula::parse_shared(&[&source, &rendered]).unwrap();
+        assert_eq!(sat::find_difference(&formulas[0], &formulas[1]), None, "{} vs {}", source, rendered);
+    }
+}
+
+#[test]
+fn covers_are_minimal()
+{
+    // Every function of three variables, against every subset of its prime implicants
+    for function in 0..256u32
+    {
+        let minterms = (0..8).filter(|minterm| function >> minterm & 1 == 1).collect::<Vec<u32>>();
+        let primes = minimize::prime_implicants(3, &minterms, minimize::DEFAULT_IMPLICANT_LIMIT).unwrap();
+        let cover = minimize::minimum_cover(3, &primes, &minterms, minimize::DEFAULT_COVER_LIMIT).unwrap();
+
+        let cost = |cover: &[Implicant]| (cover.len(), cover.iter().map(|implicant| implicant.literal_count(3)).sum::<u32>());
+
+        let best = (0..1u32 << primes.len())
+            .map(|subset| (0..primes.len()).filter(|index| subset >> index & 1 == 1).map(|index| primes[index]).collect::<Vec<Implicant>>())
+            .filter(|subset| minterms.iter().all(|minterm| subset.iter().any(|implicant| implicant.covers(*minterm))))
+            .map(|subset| cost(&subset))
+            .min()
+            .unwrap();
+
+        assert_eq!(cost(&cover), best, "function {:08b}", function);
+    }
+}
+
+#[test]
+fn renders_through_syntax_trees()
+{
+    let variables = vec![String::from("a"), String::from("b"), String::from("c")];
+
+    // The cyclic function Σm(0, 1, 2, 5, 6, 7) needs three of its six prime implicants
+    let truth = [true, true, true, false, false, true, true, true];
+    let products = minimize::minimal_sum_of_products(3, &truth, &[], minimize::DEFAULT_COVER_LIMIT).unwrap();
+    assert_eq!(products.len(), 3);
+
+    let truth = [false, false, true, true, false, false, true, true];
+    let products = minimize::minimal_sum_of_products(3, &truth, &[], minimize::DEFAULT_COVER_LIMIT).unwrap();
+    assert_eq!(execution::node_to_string(&minimize::sum_of_products_node(&products), &variables).unwrap(), "b");
+}
+
+#[test]
+fn dont_cares_are_free()
+{
+    let (source, dont_cares) = formula::split_dont_cares("(!a & b & !c) | (a & !b & !c) d(3, 5, 6, 7)").unwrap();
+    assert_eq!(dont_cares, Some(DontCares::Minterms(vec![3, 5, 6, 7])));
+
+    let formula = Formula::parse(source).unwrap();
+    let truth = table::truth_vector(&formula.groups, 3, 1).unwrap();
+    let dont_care = formula.dont_care_vector(&dont_cares.unwrap(), 1).unwrap();
+
+    let products = minimize::minimal_sum_of_products(3, &truth, &dont_care, minimize::DEFAULT_COVER_LIMIT).unwrap();
+    assert_eq!(canonical::render_sum_of_products(&formula.variables, &products), "a | b");
+
+    // The condition form marks the same rows
+    let (_, dont_cares) = formula::split_dont_cares("(!a & b & !c) | (a & !b & !c) dc: (a & b) | (a & c) | (b & c)").unwrap();
+    assert_eq!(formula.dont_care_vector(&dont_cares.unwrap(), 1).unwrap(), dont_care);
+
+    // Formulas without a clause are left alone, a variable never precedes a parenthesis
+    assert_eq!(formula::split_dont_cares("d | dc").unwrap(), ("d | dc", None));
+    assert!(formula::split_dont_cares("a d(1, x)").unwrap().1.is_none());
+    assert!(formula.dont_care_vector(&DontCares::Minterms(vec![8]), 1).is_err());
+    assert!(formula.dont_care_vector(&DontCares::Condition(String::from("z")), 1).is_err());
+}
+
+#[test]
+fn dense_functions_stop_at_the_implicant_limit()
+{
+    let size = 16;
+
+    // Constant functions are answered without tabulating
+    assert_eq!(minimize::minimal_sum_of_products(size, &vec![true; 1 << size], &[], minimize::DEFAULT_COVER_LIMIT), Ok(vec![vec![]]));
+    assert_eq!(minimize::minimal_sum_of_products(size, &vec![false; 1 << size], &[], minimize::DEFAULT_COVER_LIMIT), Ok(vec![]));
+
+    // The OR of all 16 variables has a single false row and millions of implicants
+    let truth = (0..1usize << size).map(|minterm| minterm != 0).collect::<Vec<bool>>();
+    assert_eq!(
+        minimize::minimal_sum_of_products(size, &truth, &[], minimize::DEFAULT_COVER_LIMIT),
+        Err(minimize::MinimizeError::TooManyImplicants { limit: minimize::DEFAULT_IMPLICANT_LIMIT })
+    );
+
+    let minterms = (1..1u32 << 10).collect::<Vec<u32>>();
+    assert_eq!(minimize::prime_implicants(10, &minterms, 1000), Err(minimize::MinimizeError::TooManyImplicants { limit: 1000 }));
+    assert_eq!(minimize::prime_implicants(10, &minterms, minimize::DEFAULT_IMPLICANT_LIMIT).unwrap().len(), 10);
+}
+
+#[test]
+fn constants_and_malformed_dont_cares()
+{
+    // No variables: the single row is a product without literals or nothing
+    assert_eq!(minimize::minimal_sum_of_products(0, &[true], &[], minimize::DEFAULT_COVER_LIMIT), Ok(vec![vec![]]));
+    assert_eq!(minimize::minimal_sum_of_products(0, &[false], &[], minimize::DEFAULT_COVER_LIMIT), Ok(vec![]));
+    assert_eq!(minimize::minimal_sum_of_products(0, &[false], &[true], minimize::DEFAULT_COVER_LIMIT), Ok(vec![]));
+
+    // Only don't-cares: nothing has to be covered
+    assert_eq!(minimize::minimal_sum_of_products(2, &[false; 4], &[true; 4], minimize::DEFAULT_COVER_LIMIT), Ok(vec![]));
+    assert_eq!(minimize::minimal_sum_of_products(2, &[true, false, false, false], &[false, true, true, true], minimize::DEFAULT_COVER_LIMIT), Ok(vec![vec![]]));
+
+    let formula = Formula::parse("a | b").unwrap();
+    for clause in ["a | b d(4)", "a | b dc: c", "a | b dc: &"]
+    {
+        let (_, dont_cares) = formula::split_dont_cares(clause).unwrap();
+        assert!(formula.dont_care_vector(&dont_cares.unwrap(), 1).is_err(), "{}", clause);
+    }
+    assert!(formula::split_dont_cares("a | b d(1, -2)").unwrap().1.is_none());
+}
@@ -0,0 +1,203 @@
+//! Fuzz-style checks that neither arbitrary input strings nor malformed subexpression
+//! groups can make the library panic
+
+use ttbl::compiler::{self, NodeOperation};
+use ttbl::execution::{self, EvalError};
+use ttbl::scanner::{self, OperatorType};
+use ttbl::{bytecode, table};
+use ttbl::table::RowOrdering;
+
+const FRAGMENTS: [&str; 24] = [
+    "p", "q", "r", "p1", "and", "or", "not", "T", "FALSE", "&", "|", "!", "~",
+    "=>", "<=>", "=", "<", "(", ")", "{", "}", " ", "é", "∧",
+];
+
+/// Small deterministic xorshift generator so failures are reproducible
+struct Rng(u64);
+
+impl Rng
+{
+    fn next(&mut self) -> u64
+    {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: usize) -> usize
+    {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+/// Rows are only evaluated for formulas this narrow, wider ones are checked on a single row
+const MAX_EVALUATED_VARIABLES: usize = 8;
+
+fn run_pipeline(input: &str)
+{
+    let tokens = scanner::tokenize(input);
+    let compiled = compiler::compile(&tokens);
+
+    let root = match (&compiled.error_token, &compiled.root)
+    {
+        (None, Some(root)) => root,
+        _ => return,
+    };
+
+    let groups = match execution::subexpression_groups(root)
+    {
+        Ok(groups) => groups,
+        Err(err) => {
+            assert_eq!(err, EvalError::TooDeep { max: execution::MAX_DEPTH }, "input: {:?}", input);
+            return;
+        },
+    };
+    let var_count = compiled.variables.len();
+
+    assert_eq!(execution::validate_groups(&groups, var_count), Ok(()), "input: {:?}", input);
+    assert!(execution::groups_to_string(&groups, &compiled.variables).is_ok(), "input: {:?}", input);
+
+    let mut out_eval = vec![false; groups.len()];
+
+    if var_count > table::MAX_VARIABLES as usize
+    {
+        let err = table::evaluate_table(&groups, var_count as u32, RowOrdering::default(), 2).err();
+        assert_eq!(err, Some(EvalError::TooManyVariables { count: var_count, max: table::MAX_VARIABLES as usize }), "input: {:?}", input);
+    }
+
+    if var_count > MAX_EVALUATED_VARIABLES
+    {
+        // The bytecode must agree with the interpreter on the row that alternates true and false
+        let values = (0..var_count).map(|loc| loc % 2 == 0).collect::<Vec<bool>>();
+        let program = bytecode::compile(&groups).expect(input);
+        let mut registers = vec![false; program.register_count];
+        let mut out_words = vec![false; program.outputs.len()];
+
+        execution::evaluate(&groups, &values, &mut out_eval).expect(input);
+        program.evaluate(&values, &mut registers, &mut out_words);
+        assert_eq!(out_eval, out_words, "input: {:?}", input);
+        return;
+    }
+
+    let table = table::evaluate_table(&groups, var_count as u32, RowOrdering::default(), 2).expect(input);
+    for (inputs, outputs) in table.inputs.iter().zip(table.outputs.iter())
+    {
+        execution::evaluate(&groups, inputs, &mut out_eval).expect(input);
+        assert_eq!(&out_eval, outputs, "input: {:?}", input);
+    }
+}
+
+#[test]
+fn random_inputs_never_panic()
+{
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+    for _ in 0..20_000
+    {
+        let len = rng.below(16);
+        let input: String = (0..len).map(|_| FRAGMENTS[rng.below(FRAGMENTS.len())]).collect();
+        run_pipeline(&input);
+    }
+}
+
+#[test]
+fn arbitrary_bytes_never_panic()
+{
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+
+    for _ in 0..5_000
+    {
+        let len = rng.below(24);
+        let bytes: Vec<u8> = (0..len).map(|_| rng.next() as u8).collect();
+        run_pipeline(&String::from_utf8_lossy(&bytes));
+    }
+}
+
+#[test]
+fn malformed_groups_are_rejected()
+{
+    let and = NodeOperation::BinaryOperation(OperatorType::AND);
+    let cases: Vec<(Vec<Vec<NodeOperation>>, EvalError)> = vec![
+        (vec![vec![NodeOperation::VariableDeref(0), and]], EvalError::MissingOperand { group: 0 }),
+        (vec![vec![]], EvalError::UnbalancedGroup { group: 0 }),
+        (vec![vec![NodeOperation::Literal(true), NodeOperation::Literal(false)]], EvalError::UnbalancedGroup { group: 0 }),
+        (vec![vec![NodeOperation::IndexedSubexpression(0)]], EvalError::UnresolvedSubexpression { group: 0, index: 0 }),
+        (vec![vec![NodeOperation::VariableDeref(3)]], EvalError::UnknownVariable { group: 0, location: 3 }),
+        (
+            vec![vec![NodeOperation::Literal(true), NodeOperation::UnaryOperation(OperatorType::AND)]],
+            EvalError::UnsupportedOperation { group: 0, op: NodeOperation::UnaryOperation(OperatorType::AND) }
+        ),
+    ];
+
+    let variables = vec![String::from("p")];
+    let mut out_eval = [false; 4];
+
+    for (groups, expected) in cases
+    {
+        assert_eq!(execution::validate_groups(&groups, 1), Err(expected));
+        assert_eq!(execution::evaluate(&groups, &[true], &mut out_eval), Err(expected));
+        assert_eq!(execution::groups_to_string(&groups, &variables).err(), Some(expected));
+        assert!(table::evaluate_table(&groups, 1, RowOrdering::default(), 1).is_err());
+    }
+
+    let groups = vec![vec![NodeOperation::Literal(true)], vec![NodeOperation::Literal(false)]];
+    assert_eq!(
+        execution::evaluate(&groups, &[], &mut out_eval[..1]),
+        Err(EvalError::OutputTooSmall { required: 2, available: 1 })
+    );
+    assert!(bytecode::compile(&groups).is_ok());
+}
+
+#[test]
+fn wide_formulas_never_panic()
+{
+    let mut rng = Rng(0x5bd1_e995_c2b2_ae35);
+    const OPERATORS: [&str; 4] = [" & ", " | ", " => ", " <=> "];
+
+    // Up to 300 distinct variables chained without parentheses, so every operand is still
+    // pending when the last operator is reached
+    for count in [2, 31, 32, 33, 64, 255, 256, 257, 300]
+    {
+        for _ in 0..4
+        {
+            let input = (0..count)
+                .map(|loc| format!("{}x{}", if rng.below(3) == 0 { "!" } else { "" }, loc))
+                .collect::<Vec<String>>()
+                .join(OPERATORS[rng.below(OPERATORS.len())]);
+            run_pipeline(&input);
+        }
+    }
+
+    // The same variables over and over are a wide formula with few variables
+    run_pipeline(&vec!["p"; 1000].join(" & "));
+    run_pipeline(&vec!["(p | !q)"; 1000].join(" <=> "));
+}
+
+#[test]
+fn deeply_nested_formulas_are_rejected()
+{
+    let too_deep = execution::MAX_DEPTH + 1;
+
+    let cases = [
+        format!("{}p", "!".repeat(too_deep)),
+        format!("{}p{}", "!(".repeat(too_deep), ")".repeat(too_deep)),
+        format!("{}p{}", "(p & ".repeat(too_deep), ")".repeat(too_deep)),
+        vec!["p"; too_deep + 1].join(" => "),
+        format!("{}p", "!".repeat(100_000)),
+    ];
+
+    for input in &cases
+    {
+        run_pipeline(input);
+
+        let tokens = scanner::tokenize(input);
+        let compiled = compiler::compile(&tokens);
+        let root = compiled.root.as_ref().unwrap();
+        assert_eq!(execution::subexpression_groups(root).err(), Some(EvalError::TooDeep { max: execution::MAX_DEPTH }));
+    }
+
+    // Just within the limit everything still works
+    run_pipeline(&format!("{}p", "!".repeat(execution::MAX_DEPTH - 1)));
+    run_pipeline(&vec!["p"; execution::MAX_DEPTH / 2].join(" & "));
+}
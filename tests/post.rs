@@ -0,0 +1,82 @@
+//! Post classes must have their known sizes and Post's criterion must match the classic bases
+
+use ttbl::formula::Formula;
+use ttbl::post::{self, Connective, ConnectiveError, PostClass};
+use ttbl::table;
+
+#[test]
+fn binary_functions_per_class()
+{
+    let functions = (0..16u32)
+        .map(|function| (0..4).map(|minterm| function >> minterm & 1 == 1).collect::<Vec<bool>>())
+        .collect::<Vec<Vec<bool>>>();
+
+    let count = |class: PostClass| functions.iter().filter(|truth| class.contains(truth, 2)).count();
+
+    assert_eq!(count(PostClass::PreservesFalse), 8);
+    assert_eq!(count(PostClass::PreservesTrue), 8);
+    assert_eq!(count(PostClass::Monotone), 6);
+    assert_eq!(count(PostClass::SelfDual), 4);
+    assert_eq!(count(PostClass::Affine), 8);
+}
+
+#[test]
+fn classifies_formulas()
+{
+    let classes = |source: &str| {
+        let formula = Formula::parse(source).unwrap();
+        let size = formula.variables.len() as u32;
+        post::classes(&table::truth_vector(&formula.groups, size, 1).unwrap(), size)
+    };
+
+    // The majority function is monotone and self-dual, but not affine
+    assert_eq!(
+        classes("(a & b) | (a & c) | (b & c)"),
+        vec![PostClass::PreservesFalse, PostClass::PreservesTrue, PostClass::Monotone, PostClass::SelfDual]
+    );
+    assert_eq!(classes("!(a <=> b)"), vec![PostClass::PreservesFalse, PostClass::Affine]);
+    assert_eq!(classes("a => b"), vec![PostClass::PreservesTrue]);
+}
+
+#[test]
+fn classic_bases()
+{
+    let complete = |source: &str| post::is_complete(&post::parse_connectives(source).unwrap());
+
+    for basis in ["{nand}", "{nor}", "{&, !}", "{|, not}", "{=>, 0}", "{&, ^, 1}", "{and, xor, true}"]
+    {
+        assert!(complete(basis), "{}", basis);
+    }
+
+    for basis in ["{&, |}", "{^, <=>}", "{!}", "{=>}", "{&, |, 0, 1}", "{&, ^}"]
+    {
+        assert!(!complete(basis), "{}", basis);
+    }
+}
+
+#[test]
+fn explains_missing_classes()
+{
+    let connectives = post::parse_connectives("{&, |, 0, 1}").unwrap();
+    let missing = post::completeness(&connectives).into_iter()
+        .filter(|(_, escape)| escape.is_none())
+        .map(|(class, _)| class)
+        .collect::<Vec<PostClass>>();
+
+    assert_eq!(missing, vec![PostClass::Monotone]);
+    assert_eq!(post::parse_connectives("{&, &}").unwrap(), vec![Connective::And]);
+    assert!(post::parse_connectives("{&, foo}").is_err());
+}
+
+#[test]
+fn applies_connectives_to_their_arity_only()
+{
+    assert_eq!(Connective::Nand.apply(&[true, true]), Ok(false));
+    assert_eq!(Connective::Not.apply(&[false]), Ok(true));
+    assert_eq!(Connective::True.apply(&[]), Ok(true));
+
+    assert_eq!(Connective::And.apply(&[true]), Err(ConnectiveError::Arity { connective: Connective::And, given: 1 }));
+    assert_eq!(Connective::Not.apply(&[true, false]), Err(ConnectiveError::Arity { connective: Connective::Not, given: 2 }));
+    assert_eq!(Connective::False.apply(&[false]), Err(ConnectiveError::Arity { connective: Connective::False, given: 1 }));
+    assert_eq!(ConnectiveError::Arity { connective: Connective::And, given: 1 }.to_string(), "& takes 2 argument(s), 1 given");
+}
@@ -0,0 +1,287 @@
+//! Cross-checks the SAT solver against truth tables and exercises it on formulas far too
+//! wide to tabulate
+
+use ttbl::analysis;
+use ttbl::cnf::{self, Cnf, Lit};
+use ttbl::execution::EvalError;
+use ttbl::formula::Formula;
+use ttbl::sat::{self, SatResult};
+use ttbl::table::{self, RowOrdering};
+
+mod common;
+use common::{random_formula, Rng};
+
+fn holds(formula: &Formula, values: &[bool]) -> bool
+{
+    formula.evaluate_assignment(
+        &formula.variables.iter().cloned().zip(values.iter().copied()).collect::<Vec<(String, bool)>>()
+    ).unwrap().pop().unwrap()
+}
+
+#[test]
+fn agrees_with_truth_tables()
+{
+    let mut rng = Rng(0x1234_5678_9abc_def1);
+
+    for _ in 0..2_000
+    {
+        let source = random_formula(&mut rng, 5);
+        let formula = Formula::parse(&source).unwrap();
+        let table = table::evaluate_table(&formula.groups, formula.variables.len() as u32, RowOrdering::default(), 1).unwrap();
+        let summary = analysis::classify(table.outputs.iter().map(|row| row[row.len() - 1]));
+
+        match sat::find_model(&formula)
+        {
+            Some(model) => assert!(holds(&formula, &model), "{}", source),
+            None => assert_eq!(summary.true_rows, 0, "{}", source),
+        }
+
+        match sat::find_counterexample(&formula)
+        {
+            Some(model) => assert!(!holds(&formula, &model), "{}", source),
+            None => assert_eq!(summary.false_rows, 0, "{}", source),
+        }
+    }
+}
+
+#[test]
+fn pigeonhole_is_unsatisfiable()
+{
+    // 8 pigeons in 7 holes, variable (p, h) means pigeon p sits in hole h
+    let (pigeons, holes) = (8, 7);
+    let var = |p: usize, h: usize| (p * holes + h + 1) as Lit;
+
+    let mut cnf = Cnf { num_vars: pigeons * holes, clauses: vec![] };
+    for p in 0..pigeons
+    {
+        cnf.clauses.push((0..holes).map(|h| var(p, h)).collect());
+    }
+    for h in 0..holes
+    {
+        for p in 0..pigeons
+        {
+            for q in (p + 1)..pigeons
+            {
+                cnf.clauses.push(vec![-var(p, h), -var(q, h)]);
+            }
+        }
+    }
+
+    assert_eq!(sat::solve(&cnf), SatResult::Unsat);
+}
+
+#[test]
+fn scales_to_hundreds_of_variables()
+{
+    let count = 300;
+    let chain = (1..count).map(|i| format!("(x{} <=> x{})", i, i + 1)).collect::<Vec<String>>().join(" & ");
+
+    let open = Formula::parse(&format!("{} & x1", chain)).unwrap();
+    let model = sat::find_model(&open).expect("chain with one end fixed is satisfiable");
+    assert!(model.iter().all(|val| *val));
+
+    let closed = Formula::parse(&format!("{} & x1 & !x{}", chain, count)).unwrap();
+    assert_eq!(sat::find_model(&closed), None);
+
+    let formulas = ttbl::formula::parse_shared(&[&format!("{} => (x1 <=> x{})", chain, count), "TRUE"]).unwrap();
+    assert_eq!(sat::find_difference(&formulas[0], &formulas[1]), None);
+
+    // Random satisfiable 3-SAT: every clause is built to agree with a hidden assignment
+    let mut rng = Rng(0xdead_beef_cafe_f00d);
+    let hidden = (0..count).map(|_| rng.below(2) == 1).collect::<Vec<bool>>();
+    let mut cnf = Cnf { num_vars: count, clauses: vec![] };
+
+    while cnf.clauses.len() < 4 * count
+    {
+        let clause = (0..3)
+            .map(|_| {
+                let var = rng.below(count);
+                if rng.below(2) == 1 { (var + 1) as Lit } else { -((var + 1) as Lit) }
+            })
+            .collect::<Vec<Lit>>();
+
+        if clause.iter().any(|lit| hidden[(lit.unsigned_abs() - 1) as usize] == (*lit > 0))
+        {
+            cnf.clauses.push(clause);
+        }
+    }
+
+    match sat::solve(&cnf)
+    {
+        SatResult::Sat(model) => {
+            for clause in &cnf.clauses
+            {
+                assert!(clause.iter().any(|lit| model[(lit.unsigned_abs() - 1) as usize] == (*lit > 0)));
+            }
+        },
+        SatResult::Unsat => panic!("planted 3-SAT instance reported unsatisfiable"),
+    }
+}
+
+#[test]
+fn counts_and_enumerates_models()
+{
+    let mut rng = Rng(0x0f1e_2d3c_4b5a_6978);
+
+    for _ in 0..500
+    {
+        let source = random_formula(&mut rng, 5);
+        let formula = Formula::parse(&source).unwrap();
+        let table = table::evaluate_table(&formula.groups, formula.variables.len() as u32, RowOrdering::default(), 1).unwrap();
+        let true_rows = analysis::classify(table.outputs.iter().map(|row| row[row.len() - 1])).true_rows;
+
+        assert_eq!(analysis::count_models(&formula, 4).unwrap(), true_rows as u64, "{}", source);
+
+        let everything = (0..formula.variables.len()).collect::<Vec<usize>>();
+        let models = sat::enumerate_models(&formula, &everything, None);
+        assert_eq!(models.len(), true_rows, "{}", source);
+        assert!(models.iter().all(|model| holds(&formula, model)), "{}", source);
+    }
+
+    // Wide enough to be split across several counting threads
+    let formula = Formula::parse("(a | b | c | d | e | g | h | i | j | k | l | m | n | o | p | q | r | s | u | v) & !w").unwrap();
+    assert_eq!(analysis::count_models(&formula, 8).unwrap(), (1 << 20) - 1);
+
+    // Past the row limit the rows are left to the BDD
+    let wide = (0..=analysis::MAX_COUNTED_VARIABLES).map(|loc| format!("x{}", loc)).collect::<Vec<String>>().join(" | ");
+    let formula = Formula::parse(&wide).unwrap();
+    assert_eq!(
+        analysis::count_models(&formula, 1).err(),
+        Some(EvalError::TooManyVariables { count: analysis::MAX_COUNTED_VARIABLES + 1, max: analysis::MAX_COUNTED_VARIABLES })
+    );
+
+    let formula = Formula::parse("p | q | r").unwrap();
+    assert_eq!(sat::enumerate_models(&formula, &[0], None).len(), 2);
+    assert_eq!(sat::enumerate_models(&formula, &[0, 1, 2], Some(4)).len(), 4);
+}
+
+#[test]
+fn dimacs_round_trip()
+{
+    let mut rng = Rng(0x5eed_d1ac_0bad_cafe);
+
+    for _ in 0..200
+    {
+        let source = random_formula(&mut rng, 4);
+        let formula = Formula::parse(&source).unwrap();
+        let exported = cnf::to_dimacs(&cnf::tseitin(&formula.root, formula.variables.len()), &formula.variables);
+
+        let (imported, names) = cnf::parse_dimacs(&exported).unwrap();
+        assert_eq!(names[..formula.variables.len()], formula.variables[..], "{}", source);
+
+        // Existentially quantifying the auxiliary variables gives back the original formula
+        let loaded = Formula::parse(&cnf::cnf_to_source(&imported, &names)).unwrap();
+        let projection = (0..formula.variables.len())
+            .map(|loc| loaded.variables.iter().position(|name| *name == formula.variables[loc]).unwrap())
+            .collect::<Vec<usize>>();
+        let table = table::evaluate_table(&formula.groups, formula.variables.len() as u32, RowOrdering::default(), 1).unwrap();
+        let true_rows = analysis::classify(table.outputs.iter().map(|row| row[row.len() - 1])).true_rows;
+
+        assert_eq!(sat::enumerate_models(&loaded, &projection, None).len(), true_rows, "{}", source);
+    }
+}
+
+#[test]
+fn writes_dimacs_files()
+{
+    let formula = Formula::parse("p & !q").unwrap();
+    let exported = cnf::to_dimacs(&cnf::tseitin(&formula.root, formula.variables.len()), &formula.variables);
+    assert_eq!(exported, "c generated by ttbl\nc 1 p\nc 2 q\np cnf 3 4\n-3 1 0\n-3 -2 0\n3 -1 2 0\n3 0\n");
+
+    // Constants need neither variables nor a unit clause for the constant itself
+    let expected = [("T", "p cnf 0 0\n"), ("(F)", "p cnf 0 1\n0\n"), ("!T", "p cnf 1 2\n1 0\n-1 0\n")];
+    for (source, body) in expected
+    {
+        let formula = Formula::parse(source).unwrap();
+        let exported = cnf::to_dimacs(&cnf::tseitin(&formula.root, 0), &formula.variables);
+        assert_eq!(exported, format!("c generated by ttbl\n{}", body), "{}", source);
+
+        let (imported, names) = cnf::parse_dimacs(&exported).unwrap();
+        let loaded = Formula::parse(&cnf::cnf_to_source(&imported, &names)).unwrap();
+        assert_eq!(sat::enumerate_models(&loaded, &[], None).len(), usize::from(source == "T"), "{}", source);
+    }
+}
+
+#[test]
+fn parses_dimacs_files()
+{
+    let (imported, names) = cnf::parse_dimacs("c example\np cnf 3 2\n1 -3 0\n2 3\n-1 0\n%\n0\n").unwrap();
+    assert_eq!(imported.clauses, vec![vec![1, -3], vec![2, 3, -1]]);
+    assert_eq!(names, vec!["x1", "x2", "x3"]);
+    assert_eq!(cnf::cnf_to_source(&imported, &names), "((x1 | !x3) & (x2 | x3 | !x1))");
+
+    // Names that are not valid variables are ignored
+    let (_, names) = cnf::parse_dimacs("c 1 and\np cnf 1 1\n1 0\n").unwrap();
+    assert_eq!(names, vec!["x1"]);
+
+    assert_eq!(cnf::parse_dimacs("1 2 0\n").unwrap_err().line, 1);
+    assert_eq!(cnf::parse_dimacs("p cnf 2 1\n1 3 0\n").unwrap_err().line, 2);
+    assert!(cnf::parse_dimacs("p dnf 2 1\n").is_err());
+
+    let (imported, names) = cnf::parse_dimacs("p cnf 2 0\n").unwrap();
+    assert!(Formula::parse(&cnf::cnf_to_source(&imported, &names)).is_ok());
+}
+
+#[test]
+fn renames_only_clashing_dimacs_names()
+{
+    let (_, names) = cnf::parse_dimacs("c 1 p\nc 2 and\nc 3 r\np cnf 3 1\n1 2 3 0\n").unwrap();
+    assert_eq!(names, vec!["p", "x2", "r"]);
+
+    // A name given to an earlier variable stays with it
+    let (_, names) = cnf::parse_dimacs("c 1 p\nc 2 p\nc 3 q\np cnf 3 1\n1 2 3 0\n").unwrap();
+    assert_eq!(names, vec!["p", "x2", "q"]);
+
+    // A comment name that is another variable's default name moves that default aside
+    let (_, names) = cnf::parse_dimacs("c 1 x2\nc 3 x2a\np cnf 3 1\n1 2 3 0\n").unwrap();
+    assert_eq!(names, vec!["x2", "x2aa", "x2a"]);
+}
+
+#[test]
+fn keeps_variables_declared_in_no_clause()
+{
+    let (imported, names) = cnf::parse_dimacs("p cnf 3 1\n1 0\n").unwrap();
+    let source = cnf::cnf_to_source(&imported, &names);
+    assert_eq!(source, "(x1 & ((x2 | !x2) & (x3 | !x3)))");
+
+    // Four of the eight assignments satisfy x1, as with a #SAT solver on the file
+    let formula = Formula::parse(&source).unwrap();
+    assert_eq!(formula.variables, vec!["x1", "x2", "x3"]);
+    assert_eq!(table::truth_vector(&formula.groups, 3, 1).unwrap().iter().filter(|value| **value).count(), 4);
+}
+
+#[test]
+fn formulas_without_variables()
+{
+    let truth = Formula::parse("T | F").unwrap();
+    let falsity = Formula::parse("T & F").unwrap();
+
+    assert_eq!(sat::find_model(&truth), Some(vec![]));
+    assert_eq!(sat::find_model(&falsity), None);
+    assert_eq!(sat::find_counterexample(&truth), None);
+    assert_eq!(sat::find_counterexample(&falsity), Some(vec![]));
+    assert_eq!(sat::find_difference(&truth, &falsity), Some(vec![]));
+
+    assert_eq!(sat::enumerate_models(&truth, &[], None), vec![Vec::<bool>::new()]);
+    assert!(sat::enumerate_models(&falsity, &[], None).is_empty());
+    assert_eq!(analysis::count_models(&truth, 1).unwrap(), 1);
+    assert_eq!(analysis::count_models(&falsity, 1).unwrap(), 0);
+
+    // An empty projection keeps a single model, a zero limit none
+    let formula = Formula::parse("p | q").unwrap();
+    assert_eq!(sat::enumerate_models(&formula, &[], None), vec![Vec::<bool>::new()]);
+    assert!(sat::enumerate_models(&formula, &[0, 1], Some(0)).is_empty());
+}
+
+#[test]
+fn rejects_malformed_dimacs_headers()
+{
+    for text in ["p cnf\n", "p cnf x 1\n", "p cnf 2\n", "p cnf 2 1 3\n", "p cnf 1 1\n1 0\np cnf 1 1\n"]
+    {
+        assert!(cnf::parse_dimacs(text).is_err(), "{:?}", text);
+    }
+
+    // Literals past the declared variables
+    assert!(cnf::parse_dimacs("p cnf 1 1\n-2 0\n").is_err());
+    assert!(cnf::parse_dimacs("p cnf 0 0\n").is_ok());
+}
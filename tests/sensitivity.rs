@@ -0,0 +1,77 @@
+//! Influence and derivatives must agree with the cofactors of the function and with each other
+
+use ttbl::analysis;
+use ttbl::formula::Formula;
+use ttbl::table::{self, RowOrdering};
+
+mod common;
+use common::{random_formula, Rng};
+
+fn truth_of(source: &str) -> (Vec<bool>, u32)
+{
+    let formula = Formula::parse(source).unwrap();
+    let size = formula.variables.len() as u32;
+    (table::truth_vector(&formula.groups, size, 1).unwrap(), size)
+}
+
+#[test]
+fn parity_is_fully_sensitive()
+{
+    let (truth, size) = truth_of("(a <=> b) <=> c");
+    let sensitivity = analysis::sensitivity(&truth, size);
+
+    assert_eq!(sensitivity.influence, vec![1.0, 1.0, 1.0]);
+    assert_eq!(sensitivity.rows, vec![3; 8]);
+    assert_eq!((sensitivity.average, sensitivity.max), (3.0, 3));
+}
+
+#[test]
+fn derivative_of_a_multiplexer()
+{
+    // Flipping the selector matters exactly where the two inputs differ
+    let (truth, size) = truth_of("(s & a) | (!s & b)");
+    let (expected, _) = truth_of("(s & a & !b) | (s & !a & b) | (!s & a & !b) | (!s & !a & b)");
+
+    assert_eq!(analysis::derivative(&truth, size, 0), expected);
+    assert_eq!(analysis::sensitivity(&truth, size).influence, vec![0.5, 0.5, 0.5]);
+}
+
+#[test]
+fn matches_dependencies()
+{
+    let mut rng = Rng(0x05e0_5171_7e11_0bad);
+
+    for _ in 0..300
+    {
+        let source = random_formula(&mut rng, 4);
+        let formula = Formula::parse(&source).unwrap();
+        let size = formula.variables.len() as u32;
+
+        let truth = table::truth_vector(&formula.groups, size, 1).unwrap();
+        let sensitivity = analysis::sensitivity(&truth, size);
+
+        let table = table::evaluate_table(&formula.groups, size, RowOrdering::default(), 1).unwrap();
+        let depends = analysis::dependencies(&table);
+
+        let influential = sensitivity.influence.iter().map(|influence| *influence > 0.0).collect::<Vec<bool>>();
+        assert_eq!(&influential, depends.last().unwrap(), "{}", source);
+
+        let total = sensitivity.influence.iter().sum::<f64>();
+        assert!((total - sensitivity.average).abs() < 1e-9, "{}", source);
+    }
+}
+
+#[test]
+fn constants_have_no_sensitivity()
+{
+    let sensitivity = analysis::sensitivity(&[true], 0);
+    assert!(sensitivity.influence.is_empty());
+    assert_eq!(sensitivity.rows, vec![0]);
+    assert_eq!((sensitivity.average, sensitivity.max), (0.0, 0));
+
+    let (truth, size) = truth_of("(a & !a) | (b & F) | (c & !c)");
+    let sensitivity = analysis::sensitivity(&truth, size);
+    assert_eq!(sensitivity.influence, vec![0.0; 3]);
+    assert_eq!(sensitivity.max, 0);
+    assert_eq!(analysis::derivative(&truth, size, 1), vec![false; 8]);
+}
@@ -0,0 +1,102 @@
+//! Every simplification step must keep the formula equivalent and apply the law it names
+
+use ttbl::compiler::NodeOperation;
+use ttbl::execution;
+use ttbl::formula::Formula;
+use ttbl::sat;
+use ttbl::simplify::{self, Law};
+
+mod common;
+use common::{random_formula, Rng};
+
+fn simplified(source: &str) -> (Vec<Law>, String)
+{
+    let formula = Formula::parse(source).unwrap();
+    let steps = simplify::simplify(&formula.root, simplify::DEFAULT_STEP_LIMIT);
+    let last = steps.last().expect("No step applied");
+
+    let result = match last.result.op
+    {
+        NodeOperation::Literal(val) => String::from(if val { "TRUE" } else { "FALSE" }),
+        _ => execution::node_to_string(&last.result, &formula.variables).unwrap(),
+    };
+
+    (steps.iter().map(|step| step.law).collect(), result)
+}
+
+#[test]
+fn steps_stay_equivalent()
+{
+    let mut rng = Rng(0x51ba_1f1e_d0ff_ba11);
+
+    for _ in 0..200
+    {
+        let source = random_formula(&mut rng, 4);
+        let formula = Formula::parse(&source).unwrap();
+        let steps = simplify::simplify(&formula.root, simplify::DEFAULT_STEP_LIMIT);
+
+        assert!(steps.len() < simplify::DEFAULT_STEP_LIMIT, "{}", source);
+
+        for step in &steps
+        {
+            let difference = sat::find_node_difference(&formula.root, &step.result, formula.variables.len());
+            assert_eq!(difference, None, "{} after {}", source, step.law);
+        }
+    }
+}
+
+#[test]
+fn names_the_laws()
+{
+    assert_eq!(simplified("a => b"), (vec![Law::ImplicationElimination], String::from("(!(a) | b)")));
+    assert_eq!(simplified("a & (a | b)"), (vec![Law::Absorption], String::from("a")));
+    assert_eq!(simplified("(a => b) & a"), (vec![Law::ImplicationElimination, Law::Absorption], String::from("(b & a)")));
+    assert_eq!(
+        simplified("(a & b) | (a & !b)"),
+        (vec![Law::Distribution, Law::Complement, Law::Identity], String::from("a"))
+    );
+    assert_eq!(
+        simplified("!(!a | !b)"),
+        (vec![Law::DeMorgan, Law::DoubleNegation, Law::DoubleNegation], String::from("(a & b)"))
+    );
+    assert_eq!(simplified("(a | F) & (b | !b)").1, "a");
+    assert_eq!(simplified("a & !a & b").1, "FALSE");
+}
+
+#[test]
+fn stops_at_the_limit()
+{
+    let formula = Formula::parse("(a <=> b) <=> c").unwrap();
+    assert_eq!(simplify::simplify(&formula.root, 2).len(), 2);
+
+    let simple = Formula::parse("a & b").unwrap();
+    assert!(simplify::simplify(&simple.root, simplify::DEFAULT_STEP_LIMIT).is_empty());
+}
+
+#[test]
+fn complements_written_out_by_de_morgan()
+{
+    // `a & !b` is the complement of `!a | b`, the laws reach the simplest form
+    assert_eq!(simplified("!(a => b) | (a <=> b)").1, "(a | !(b))");
+    assert_eq!(simplified("(a & !b) | (!a | b)").1, "TRUE");
+    assert_eq!(simplified("(a | b) & (!b & !a)").1, "FALSE");
+    assert_eq!(simplified("(a & !b) | ((b | !a) & c)"), (vec![Law::Absorption], String::from("((a & !(b)) | c)")));
+
+    // Operands have to complement each other one for one
+    let formula = Formula::parse("(a & b) | (!a | c)").unwrap();
+    let steps = simplify::simplify(&formula.root, simplify::DEFAULT_STEP_LIMIT);
+    assert!(steps.iter().all(|step| step.law != Law::Complement));
+}
+
+#[test]
+fn constant_formulas()
+{
+    assert_eq!(simplified("T & F").1, "FALSE");
+    assert_eq!(simplified("!(T => F)"), (vec![Law::ImplicationElimination, Law::DeMorgan, Law::DoubleNegation, Law::Complement, Law::Identity], String::from("TRUE")));
+
+    for source in ["T", "F", "a"]
+    {
+        let formula = Formula::parse(source).unwrap();
+        assert!(simplify::simplify(&formula.root, simplify::DEFAULT_STEP_LIMIT).is_empty(), "{}", source);
+    }
+}
@@ -0,0 +1,145 @@
+//! Row orderings must produce the documented sequences and tables must not depend on how many
+//! threads build them
+
+use ttbl::formula::Formula;
+use ttbl::table::{self, BoolPermutationsIterator, RowOrder, RowOrdering};
+
+#[test]
+fn threads_agree_with_a_single_thread()
+{
+    // 2^14 rows, enough for four workers of at least 4096 rows each
+    let formula = Formula::parse("((a & b) | !(c => d)) <=> ((e | o) & (g <=> h) & !(i & j)) | (k => (l & m & n))").unwrap();
+    let size = formula.variables.len() as u32;
+    assert_eq!(size, 14);
+
+    for order in [RowOrder::Binary, RowOrder::AllTrueFirst, RowOrder::Gray]
+    {
+        for reversed in [false, true]
+        {
+            let ordering = RowOrdering { order, reversed };
+            let single = table::evaluate_table(&formula.groups, size, ordering, 1).unwrap();
+
+            for threads in [2, 3, 4, 16]
+            {
+                let threaded = table::evaluate_table(&formula.groups, size, ordering, threads).unwrap();
+                assert_eq!(threaded.inputs, single.inputs, "{:?} with {} threads", ordering, threads);
+                assert_eq!(threaded.outputs, single.outputs, "{:?} with {} threads", ordering, threads);
+            }
+        }
+    }
+}
+
+#[test]
+fn truth_vectors_match_the_last_column()
+{
+    // Fewer variables than lanes in a word, exactly one word, and enough words for several workers
+    let sources = ["p", "!p => q", "(a | b) & (c <=> d) & (e => o)", "((a & b) | !(c => d)) <=> ((e | o) & (g <=> h) & !(i & j)) | (k => (l & m & n))"];
+
+    for source in sources
+    {
+        let formula = Formula::parse(source).unwrap();
+        let size = formula.variables.len() as u32;
+        let ordering = RowOrdering { order: RowOrder::Binary, reversed: false };
+        let table = table::evaluate_table(&formula.groups, size, ordering, 1).unwrap();
+        let expected: Vec<bool> = table.outputs.iter().map(|row| row[row.len() - 1]).collect();
+
+        for threads in [1, 4]
+        {
+            assert_eq!(table::truth_vector(&formula.groups, size, threads).unwrap(), expected, "{} with {} threads", source, threads);
+        }
+    }
+}
+
+#[test]
+fn result_vectors_do_not_depend_on_the_row_order()
+{
+    let formula = Formula::parse("(a => b) <=> (c | !d)").unwrap();
+    let expected = table::truth_vector(&formula.groups, 4, 1).unwrap();
+
+    for order in [RowOrder::Binary, RowOrder::AllTrueFirst, RowOrder::Gray]
+    {
+        for reversed in [false, true]
+        {
+            let ordering = RowOrdering { order, reversed };
+            let table = table::evaluate_table(&formula.groups, 4, ordering, 1).unwrap();
+            assert_eq!(table.result_vector(), expected, "{:?}", ordering);
+        }
+    }
+}
+
+fn rows(size: u32, ordering: RowOrdering) -> Vec<Vec<bool>>
+{
+    let mut iter = BoolPermutationsIterator::with_ordering(size, ordering);
+    let mut rows = vec![];
+
+    while !iter.finished()
+    {
+        iter.advance();
+        rows.push(iter.get().clone());
+    }
+
+    rows
+}
+
+#[test]
+fn orderings_produce_their_row_sequence()
+{
+    let minterms = |order: RowOrder, reversed: bool| {
+        let ordering = RowOrdering { order, reversed };
+        (0..8).map(|position| ordering.minterm_at(3, position)).collect::<Vec<u32>>()
+    };
+
+    assert_eq!(minterms(RowOrder::Binary, false), vec![0, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(minterms(RowOrder::Binary, true), vec![7, 6, 5, 4, 3, 2, 1, 0]);
+    assert_eq!(minterms(RowOrder::AllTrueFirst, false), vec![7, 6, 5, 4, 3, 2, 1, 0]);
+    assert_eq!(minterms(RowOrder::AllTrueFirst, true), vec![0, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(minterms(RowOrder::Gray, false), vec![0, 1, 3, 2, 6, 7, 5, 4]);
+    assert_eq!(minterms(RowOrder::Gray, true), vec![4, 5, 7, 6, 2, 3, 1, 0]);
+
+    // The first variable is the most significant bit of the minterm
+    let binary = rows(2, RowOrdering { order: RowOrder::Binary, reversed: false });
+    assert_eq!(binary, vec![vec![false, false], vec![false, true], vec![true, false], vec![true, true]]);
+
+    let gray = rows(2, RowOrdering { order: RowOrder::Gray, reversed: false });
+    assert_eq!(gray, vec![vec![false, false], vec![false, true], vec![true, true], vec![true, false]]);
+    assert_eq!(rows(2, RowOrdering::default()), binary.into_iter().rev().collect::<Vec<Vec<bool>>>());
+}
+
+#[test]
+fn gray_rows_differ_in_one_variable()
+{
+    for size in 1..=10
+    {
+        for reversed in [false, true]
+        {
+            let rows = rows(size, RowOrdering { order: RowOrder::Gray, reversed });
+
+            // Every assignment appears exactly once
+            let mut sorted = rows.clone();
+            sorted.sort();
+            sorted.dedup();
+            assert_eq!(sorted.len(), 1 << size);
+
+            for pair in rows.windows(2)
+            {
+                let changed = pair[0].iter().zip(pair[1].iter()).filter(|(a, b)| a != b).count();
+                assert_eq!(changed, 1, "{:?} after {:?}", pair[1], pair[0]);
+            }
+        }
+    }
+}
+
+#[test]
+fn ranges_match_the_full_iteration()
+{
+    let ordering = RowOrdering { order: RowOrder::Gray, reversed: true };
+    let all = rows(4, ordering);
+
+    let mut iter = BoolPermutationsIterator::with_range(4, ordering, 5, 9);
+    for expected in &all[5..9]
+    {
+        iter.advance();
+        assert_eq!(iter.get(), expected);
+    }
+    assert!(iter.finished());
+}
@@ -0,0 +1,75 @@
+//! Structural conversions must preserve the function of the formula
+
+use ttbl::canonical;
+use ttbl::execution;
+use ttbl::formula::{self, Formula};
+use ttbl::sat;
+use ttbl::table;
+use ttbl::transform::{self, TransformError};
+
+mod common;
+use common::{random_formula, Rng};
+
+fn truth(groups: &[Vec<ttbl::compiler::NodeOperation>], var_count: usize) -> Vec<bool>
+{
+    table::truth_vector(groups, var_count as u32, 1).unwrap()
+}
+
+#[test]
+fn conversions_preserve_the_function()
+{
+    let mut rng = Rng(0xabcd_ef01_2345_6789);
+
+    for _ in 0..300
+    {
+        let source = random_formula(&mut rng, 4);
+        let formula = Formula::parse(&source).unwrap();
+        let var_count = formula.variables.len();
+        let expected = truth(&formula.groups, var_count);
+
+        for converted in [
+            transform::eliminate_implications(&formula.root, 100_000).unwrap(),
+            transform::to_nnf(&formula.root, 100_000).unwrap(),
+        ]
+        {
+            assert_eq!(truth(&execution::subexpression_groups(&converted).unwrap(), var_count), expected, "{}", source);
+        }
+
+        let cnf = canonical::render_product_of_sums(&formula.variables, &transform::to_cnf(&formula.root, 100_000).unwrap());
+        let dnf = canonical::render_sum_of_products(&formula.variables, &transform::to_dnf(&formula.root, 100_000).unwrap());
+
+        for rendered in [cnf, dnf]
+        {
+            let formulas = formula::parse_shared(&[&source, &rendered]).expect(&rendered);
+            assert_eq!(sat::find_difference(&formulas[0], &formulas[1]), None, "{} vs {}", source, rendered);
+        }
+    }
+}
+
+#[test]
+fn size_guard_stops_blow_up()
+{
+    let source = (1..=20).map(|i| format!("(a{} & b{})", i, i)).collect::<Vec<String>>().join(" | ");
+    let formula = Formula::parse(&source).unwrap();
+
+    assert_eq!(transform::to_cnf(&formula.root, 10_000), Err(TransformError::TooLarge { limit: 10_000 }));
+    assert_eq!(transform::to_dnf(&formula.root, 10_000).unwrap().len(), 20);
+}
+
+#[test]
+fn constants_convert_to_empty_forms()
+{
+    let convert = |source: &str| {
+        let formula = Formula::parse(source).unwrap();
+        (transform::to_cnf(&formula.root, 10).unwrap(), transform::to_dnf(&formula.root, 10).unwrap())
+    };
+
+    // TRUE has no clauses and a product without literals, FALSE the other way around
+    assert_eq!(convert("T | p"), (vec![], vec![vec![]]));
+    assert_eq!(convert("F & p"), (vec![vec![]], vec![]));
+    assert!(convert("p | !p").0.is_empty());
+
+    // Negations are pushed into the constants, which are not folded
+    let formula = Formula::parse("!(T => F)").unwrap();
+    assert_eq!(execution::node_to_string(&transform::to_nnf(&formula.root, 10).unwrap(), &[]).unwrap(), "(<T> & <T>)");
+}